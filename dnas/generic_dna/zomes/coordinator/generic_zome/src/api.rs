@@ -1,6 +1,15 @@
-use crate::{derive_link_tag, NodeLink, NodeLinkMeta, Signal, SignalKind, Thing};
+use crate::batch::{get_latest_things_batched, get_links_many, get_many, get_node_contents};
+use crate::chain::query_my_links_to_thing;
+use crate::expiry::is_expired;
+use crate::sort::{sort_linked_nodes, SortBy, SortKey, SortableLinkedNode};
+use crate::subscriptions::SUBSCRIBER_TAG;
+use crate::trash::{
+    are_trashed, without_trashed, without_trashed_links, without_trashed_nodes, TRASHED_TAG,
+};
+use crate::{derive_link_tag, NodeLinkMeta, Thing, ThingDeletion};
 use generic_zome_integrity::*;
 use hdk::prelude::*;
+use std::collections::HashSet;
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(tag = "type")]
@@ -29,25 +38,131 @@ pub struct LinkInput {
     pub direction: LinkDirection,
     pub node_id: NodeId,
     pub tag: Option<Vec<u8>>,
+    /// If true, no new link is created if a link with the same tag already
+    /// exists between the two nodes in the given direction
+    #[serde(default)]
+    pub dedupe: bool,
+    /// Fractional index key of the link if it is part of an ordered
+    /// collection. Use `insert_after`, `insert_before` or `move_item` to
+    /// have it generated.
+    #[serde(default)]
+    pub position: Option<String>,
+    /// If set, the link (and its backlink) is treated as absent from this
+    /// time on and gets deleted by the scheduled cleanup
+    #[serde(default)]
+    pub expires_at: Option<Timestamp>,
+}
+
+/// Argument of the read externs: either the plain ids the extern took before
+/// it had options, or its full input. Callers that pass the plain ids keep
+/// working and get the default options.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum ReadInput<P, I> {
+    Plain(P),
+    WithOptions(I),
+}
+
+impl<P: Into<I>, I> ReadInput<P, I> {
+    pub fn into_input(self) -> I {
+        match self {
+            ReadInput::Plain(plain) => plain.into(),
+            ReadInput::WithOptions(input) => input,
+        }
+    }
+}
+
+/// Fails if an option is set that the extern doesn't use, rather than
+/// silently ignoring it
+fn reject_unused_options(extern_name: &str, options: &[(&str, bool)]) -> ExternResult<()> {
+    match options.iter().find(|(_, is_set)| *is_set) {
+        Some((option, _)) => Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "{extern_name} doesn't support the {option} option."
+        )))),
+        None => Ok(()),
+    }
 }
 
 // This just forwards the hdk get that can be called to make sure a certain
 // hash that is konwn about via remote signal gets fetched and will therefore be
 // returned with the next polling cycle
 #[hdk_extern]
-pub fn get_records(hashes: Vec<AnyDhtHash>) -> ExternResult<Vec<Option<Record>>> {
-    let get_input: Vec<GetInput> = hashes
-        .into_iter()
-        .map(|hash| Ok(GetInput::new(hash, GetOptions::default())))
-        .collect::<ExternResult<Vec<GetInput>>>()?;
-    let records = HDK.with(|hdk| hdk.borrow().get(get_input))?;
-    Ok(records)
+pub fn get_records(
+    input: ReadInput<Vec<AnyDhtHash>, GetRecordsInput>,
+) -> ExternResult<Vec<Option<Record>>> {
+    let input = input.into_input();
+    get_many(
+        input.hashes,
+        input.get_strategy.unwrap_or(GetStrategy::Network),
+    )
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetRecordsInput {
+    pub hashes: Vec<AnyDhtHash>,
+    /// Whether to fetch from the network or only from the local databases.
+    /// Defaults to Network.
+    pub get_strategy: Option<GetStrategy>,
+}
+
+impl From<Vec<AnyDhtHash>> for GetRecordsInput {
+    fn from(hashes: Vec<AnyDhtHash>) -> Self {
+        GetRecordsInput {
+            hashes,
+            get_strategy: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetThingInput {
+    pub thing_id: ActionHash,
+    /// Whether to fetch from the network or only from the local databases.
+    /// Local returns cached data without waiting for other peers. Defaults
+    /// to Network.
+    pub get_strategy: Option<GetStrategy>,
+    /// Whether to return the Thing even if it has been moved to the trash.
+    /// Not supported by get_thing_details, which returns trashed Things.
+    pub include_trashed: Option<bool>,
+}
+
+impl From<ActionHash> for GetThingInput {
+    fn from(thing_id: ActionHash) -> Self {
+        GetThingInput {
+            thing_id,
+            get_strategy: None,
+            include_trashed: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetThingsInput {
+    pub thing_ids: Vec<ActionHash>,
+    /// Defaults to Network
+    pub get_strategy: Option<GetStrategy>,
+    /// Whether to return Things that have been moved to the trash
+    pub include_trashed: Option<bool>,
+}
+
+impl From<Vec<ActionHash>> for GetThingsInput {
+    fn from(thing_ids: Vec<ActionHash>) -> Self {
+        GetThingsInput {
+            thing_ids,
+            get_strategy: None,
+            include_trashed: None,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CreateThingInput {
     pub content: String,
     pub links: Option<Vec<LinkInput>>,
+    /// If set, the Thing is treated as absent from this time on and gets
+    /// deleted by the scheduled cleanup
+    #[serde(default)]
+    pub expires_at: Option<Timestamp>,
 }
 
 #[hdk_extern]
@@ -55,121 +170,198 @@ pub fn create_thing(input: CreateThingInput) -> ExternResult<Thing> {
     // 1. Create the Thing entry
     let thing_id = create_entry(&EntryTypes::Thing(ThingEntry {
         content: input.content.clone(),
+        expires_at: input.expires_at,
     }))?;
 
     let thing_record = get(thing_id.clone(), GetOptions::default())?.ok_or(wasm_error!(
         WasmErrorInner::Guest("Failed to get record that was just created.".into())
     ))?;
 
-    let mut links_created: Vec<NodeLinkMeta> = Vec::new();
-
     // 2. Create all links as necessary
-    match input.links.clone() {
-        Some(links) => {
-            for link in links {
-                let (node_link, maybe_backlink) =
-                    create_link_from_node_by_id(NodeId::Thing(thing_id.clone()), link.clone())?;
-                links_created.push(node_link);
-                if let Some(backlink) = maybe_backlink {
-                    links_created.push(backlink);
-                }
-            }
+    if let Some(links) = input.links {
+        for link in links {
+            create_link_from_node_by_id(NodeId::Thing(thing_id.clone()), link)?;
         }
-        None => (),
     }
 
-    let thing = Thing {
+    // Signals to the frontend are emitted in post_commit
+    Ok(Thing {
         id: thing_id,
         content: input.content,
         creator: thing_record.action().author().clone(),
         created_at: thing_record.action().timestamp(),
         updated_at: None,
-    };
+        deleted: None,
+        expires_at: input.expires_at,
+    })
+}
 
-    // 3. Emit signals to the frontend
-    emit_signal(Signal::Local(SignalKind::ThingCreated {
-        thing: thing.clone(),
-    }))?;
-    if let Some(_) = input.links.clone() {
-        emit_signal(Signal::Local(SignalKind::LinksCreated {
-            links: links_created,
-        }))?;
-    }
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type")]
+pub enum ThingStatus {
+    Live,
+    /// The Thing has been deleted. If it has been deleted multiple times,
+    /// this is the earliest delete.
+    Deleted {
+        by: AgentPubKey,
+        at: Timestamp,
+    },
+    /// The latest version of the Thing has expired, so it is treated as
+    /// absent even though it has not been deleted (yet)
+    Expired {
+        at: Timestamp,
+    },
+    /// Either the Thing never existed or it has not reached this agent yet
+    NotFound,
+}
 
-    Ok(thing)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ThingDetails {
+    pub status: ThingStatus,
+    /// All versions of the Thing, starting with the original one. Also
+    /// returned for deleted Things. Empty if the Thing was not found.
+    pub revisions: Vec<Thing>,
 }
 
-/// Gets the latest known version of a Thing
+/// Gets the status of a Thing together with all its versions. Unlike
+/// get_latest_thing, this tells deleted Things apart from ones that can't
+/// be found.
 #[hdk_extern]
-pub fn get_latest_thing(thing_id: ActionHash) -> ExternResult<Option<Thing>> {
-    let original_thing = get_original_thing(thing_id.clone())?;
-    match original_thing {
-        Some(thing) => {
-            let links = get_links(
-                GetLinksInputBuilder::try_new(thing_id.clone(), LinkTypes::ThingUpdates)?.build(),
-            )?;
-            let thing_record = get_latest_thing_from_links(links)?;
-            match thing_record {
-                Some(r) => Ok(Some(thing_record_to_thing(r, thing)?)),
-                None => {
-                    let maybe_original_record = get(thing_id, GetOptions::default())?;
-                    match maybe_original_record {
-                        Some(r) => Ok(Some(thing_record_to_thing(r, thing)?)),
-                        None => Ok(None),
-                    }
-                }
-            }
+pub fn get_thing_details(
+    input: ReadInput<ActionHash, GetThingInput>,
+) -> ExternResult<ThingDetails> {
+    let input = input.into_input();
+    reject_unused_options(
+        "get_thing_details",
+        &[("include_trashed", input.include_trashed.is_some())],
+    )?;
+    let strategy = input.get_strategy.unwrap_or(GetStrategy::Network);
+    let record_details = match get_details(input.thing_id, GetOptions { strategy })? {
+        Some(Details::Record(record_details)) => record_details,
+        Some(Details::Entry(_)) => {
+            return Err(wasm_error!(WasmErrorInner::Guest(
+                "Expected the details of a record for a thing_id (action hash).".into()
+            )))
         }
-        None => Ok(None),
-    }
+        None => {
+            return Ok(ThingDetails {
+                status: ThingStatus::NotFound,
+                revisions: Vec::new(),
+            })
+        }
+    };
+    let deleted = record_details
+        .deletes
+        .iter()
+        .min_by_key(|delete| delete.action().timestamp())
+        .map(|delete| ThingDeletion {
+            by: delete.action().author().clone(),
+            at: delete.action().timestamp(),
+        });
+    let original_thing = Thing {
+        deleted: deleted.clone(),
+        ..original_thing_record_to_thing(record_details.record)?
+    };
+    let revisions = get_revisions(original_thing, strategy)?;
+    let expires_at = revisions
+        .iter()
+        .max_by_key(|thing| thing.updated_at)
+        .and_then(|thing| thing.expires_at);
+    let status = match (deleted, expires_at) {
+        (Some(deletion), _) => ThingStatus::Deleted {
+            by: deletion.by,
+            at: deletion.at,
+        },
+        (None, Some(at)) if is_expired(Some(at), sys_time()?) => ThingStatus::Expired { at },
+        (None, _) => ThingStatus::Live,
+    };
+    Ok(ThingDetails { status, revisions })
 }
 
-fn get_latest_thing_from_links(mut links: Vec<Link>) -> ExternResult<Option<Record>> {
-    links.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
-
-    for link in links {
-        if let Some(thing_id) = link.target.into_action_hash() {
-            let maybe_record = get(thing_id, GetOptions::default())?;
-            if let Some(record) = maybe_record {
-                return Ok(Some(record));
-            }
-        }
-    }
-    Ok(None)
+/// Gets the latest known version of a Thing
+#[hdk_extern]
+pub fn get_latest_thing(
+    input: ReadInput<ActionHash, GetThingInput>,
+) -> ExternResult<Option<Thing>> {
+    let input = input.into_input();
+    Ok(get_latest_things(ReadInput::WithOptions(GetThingsInput {
+        thing_ids: vec![input.thing_id],
+        get_strategy: input.get_strategy,
+        include_trashed: input.include_trashed,
+    }))?
+    .pop()
+    .flatten())
 }
 
 /// For a vector of provided thing ids, get all the respective latest known Thing
 #[hdk_extern]
-pub fn get_latest_things(thing_ids: Vec<ActionHash>) -> ExternResult<Vec<Option<Thing>>> {
-    let mut latest_things: Vec<Option<Thing>> = Vec::new();
-    for thing_id in thing_ids {
-        let maybe_thing = get_latest_thing(thing_id)?;
-        latest_things.push(maybe_thing);
+pub fn get_latest_things(
+    input: ReadInput<Vec<ActionHash>, GetThingsInput>,
+) -> ExternResult<Vec<Option<Thing>>> {
+    let input = input.into_input();
+    let strategy = input.get_strategy.unwrap_or(GetStrategy::Network);
+    let things = get_latest_things_batched(input.thing_ids, strategy)?;
+    match input.include_trashed.unwrap_or(false) {
+        true => Ok(things),
+        false => without_trashed(things, strategy),
     }
-    Ok(latest_things)
 }
 
+/// Gets the original version of a Thing. Like get_latest_thing, this
+/// returns None for expired Things and, unless include_trashed is set, for
+/// trashed ones.
 #[hdk_extern]
-pub fn get_original_thing(original_thing_id: ActionHash) -> ExternResult<Option<Thing>> {
-    let maybe_thing_record = get(original_thing_id.clone(), GetOptions::default())?;
-    match maybe_thing_record {
-        Some(record) => {
-            let thing = original_thing_record_to_thing(record)?;
-            Ok(Some(thing))
-        }
-        None => Ok(None),
+pub fn get_original_thing(
+    input: ReadInput<ActionHash, GetThingInput>,
+) -> ExternResult<Option<Thing>> {
+    let input = input.into_input();
+    let strategy = input.get_strategy.unwrap_or(GetStrategy::Network);
+    let maybe_thing_record = get(input.thing_id.clone(), GetOptions { strategy })?;
+    let Some(record) = maybe_thing_record else {
+        return Ok(None);
+    };
+    let thing = original_thing_record_to_thing(record)?;
+    if is_expired(thing.expires_at, sys_time()?) {
+        return Ok(None);
+    }
+    if !input.include_trashed.unwrap_or(false)
+        && are_trashed(&[input.thing_id], strategy)?
+            .first()
+            .copied()
+            .unwrap_or_default()
+    {
+        return Ok(None);
     }
+    Ok(Some(thing))
 }
 
 #[hdk_extern]
-pub fn get_all_revisions_for_thing(thing_id: ActionHash) -> ExternResult<Vec<Thing>> {
-    let Some(original_thing) = get_original_thing(thing_id.clone())? else {
+pub fn get_all_revisions_for_thing(
+    input: ReadInput<ActionHash, GetThingInput>,
+) -> ExternResult<Vec<Thing>> {
+    let input = input.into_input();
+    let strategy = input.get_strategy.unwrap_or(GetStrategy::Network);
+    let Some(original_thing) = get_original_thing(ReadInput::WithOptions(GetThingInput {
+        thing_id: input.thing_id,
+        get_strategy: Some(strategy),
+        include_trashed: input.include_trashed,
+    }))?
+    else {
         return Err(wasm_error!(WasmErrorInner::Guest(
             "No original Thing found for this thing_id (action hash).".into()
         )));
     };
+    get_revisions(original_thing, strategy)
+}
+
+/// Gets all versions of a Thing, starting with the original one. Updates
+/// inherit the deleted info of the original Thing.
+fn get_revisions(original_thing: Thing, strategy: GetStrategy) -> ExternResult<Vec<Thing>> {
+    let thing_id = original_thing.id.clone();
     let links = get_links(
-        GetLinksInputBuilder::try_new(thing_id.clone(), LinkTypes::ThingUpdates)?.build(),
+        GetLinksInputBuilder::try_new(thing_id.clone(), LinkTypes::ThingUpdates)?
+            .get_options(strategy)
+            .build(),
     )?;
     let get_input: Vec<GetInput> = links
         .into_iter()
@@ -181,7 +373,7 @@ pub fn get_all_revisions_for_thing(thing_id: ActionHash) -> ExternResult<Vec<Thi
                         "No action hash associated with link".to_string()
                     )))?
                     .into(),
-                GetOptions::default(),
+                GetOptions { strategy },
             ))
         })
         .collect::<ExternResult<Vec<GetInput>>>()?;
@@ -189,8 +381,7 @@ pub fn get_all_revisions_for_thing(thing_id: ActionHash) -> ExternResult<Vec<Thi
     let records: Vec<Record> = records.into_iter().flatten().collect();
     let mut things = records
         .into_iter()
-        .map(|r| thing_record_to_thing(r, original_thing.clone()).ok())
-        .filter_map(|t| t)
+        .filter_map(|r| thing_record_to_thing(r, original_thing.clone()).ok())
         .collect::<Vec<Thing>>();
     things.insert(0, original_thing);
     Ok(things)
@@ -208,11 +399,18 @@ pub fn update_thing(input: UpdateThingInput) -> ExternResult<Thing> {
         get(input.thing_id.clone(), GetOptions::default())?.ok_or(wasm_error!(
             WasmErrorInner::Guest("Failed to get record of original Thing.".into())
         ))?;
+    // The expiry time is set on creation and carried over to all updates
+    let expires_at = original_thing_record
+        .entry()
+        .to_app_option::<ThingEntry>()
+        .map_err(|e| wasm_error!(e))?
+        .and_then(|thing_entry| thing_entry.expires_at);
 
     let updated_thing_hash = update_entry(
         input.thing_id.clone(),
         &EntryTypes::Thing(ThingEntry {
             content: input.updated_content.clone(),
+            expires_at,
         }),
     )?;
 
@@ -222,29 +420,23 @@ pub fn update_thing(input: UpdateThingInput) -> ExternResult<Thing> {
         )),
     )?;
 
-    let update_link_action_hash = create_link(
+    create_link(
         input.thing_id.clone(),
-        updated_thing_hash.clone(),
+        updated_thing_hash,
         LinkTypes::ThingUpdates,
         (),
     )?;
 
-    let thing = Thing {
+    // Signals to the frontend are emitted in post_commit
+    Ok(Thing {
         id: input.thing_id,
         content: input.updated_content,
         creator: original_thing_record.action().author().clone(),
         created_at: original_thing_record.action().timestamp(),
         updated_at: Some(updated_thing_record.action().timestamp()),
-    };
-
-    // 3. Emit signals to the frontend
-    emit_signal(Signal::Local(SignalKind::ThingUpdated {
-        thing: thing.clone(),
-        update_action_hash: updated_thing_hash,
-        update_link_action_hash,
-    }))?;
-
-    Ok(thing)
+        deleted: None,
+        expires_at,
+    })
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -255,7 +447,11 @@ pub struct DeleteThingInput {
     pub delete_links: Option<Vec<LinkInput>>,
 }
 
-/// Deletes a thing and all associated links and backlinks
+/// Deletes a Thing. With delete_backlinks, the backlinks of its bidirectional
+/// links and the links pointing to it are deleted as well, as far as they can
+/// be found: through backlinks stored at the Thing, as From links stored at
+/// it, or in the calling agent's source chain. With delete_links_from_creator,
+/// the links from the creator's agent node to the Thing are deleted.
 #[hdk_extern]
 pub fn delete_thing(input: DeleteThingInput) -> ExternResult<()> {
     let thing_record = match get(input.thing_id.clone(), GetOptions::default())? {
@@ -267,270 +463,878 @@ pub fn delete_thing(input: DeleteThingInput) -> ExternResult<()> {
         }
     };
 
-    let mut links_deleted: Vec<NodeLink> = Vec::new();
-
     // 1. Delete the original Thing entry (don't care about updates as they are anyway
     // not retreivable without the original Thing entry)
     delete_entry(input.thing_id.clone())?;
 
-    // 2. Delete all backlinks from bidirectional links. We do NOT delete links pointing away from it.
+    let mut links_to_delete: Vec<ActionHash> = Vec::new();
+
+    // 2. Delete all backlinks from bidirectional links as well as the links pointing
+    // to the Thing. We do NOT delete links pointing away from it.
     if input.delete_backlinks {
-        let links_to_agents = get_links(
-            GetLinksInputBuilder::try_new(input.thing_id.clone(), LinkTypes::ToAgent)?.build(),
-        )?;
-        for link in links_to_agents {
-            let link_tag_content = deserialize_link_tag(link.tag.0)?;
-            if let Some(backlink_action_hash) = link_tag_content.backlink_action_hash {
-                delete_link(backlink_action_hash.clone())?;
-                links_deleted.push(NodeLink {
-                    src: link_tag_content.target_node_id,
-                    dst: NodeId::Thing(input.thing_id.clone()),
-                    tag: link_tag_content.tag,
-                    create_action_hash: backlink_action_hash,
-                });
-            }
-        }
-        let links_to_things = get_links(
-            GetLinksInputBuilder::try_new(input.thing_id.clone(), LinkTypes::ToAgent)?.build(),
-        )?;
-        for link in links_to_things {
-            let link_tag_content = deserialize_link_tag(link.tag.0)?;
-            if let Some(backlink_action_hash) = link_tag_content.backlink_action_hash {
-                delete_link(backlink_action_hash.clone())?;
-                links_deleted.push(NodeLink {
-                    src: link_tag_content.target_node_id,
-                    dst: NodeId::Thing(input.thing_id.clone()),
-                    tag: link_tag_content.tag,
-                    create_action_hash: backlink_action_hash,
-                });
-            }
-        }
-        let links_to_anchors = get_links(
-            GetLinksInputBuilder::try_new(input.thing_id.clone(), LinkTypes::ToAgent)?.build(),
-        )?;
-        for link in links_to_anchors {
-            let link_tag_content = deserialize_link_tag(link.tag.0)?;
-            if let Some(backlink_action_hash) = link_tag_content.backlink_action_hash {
-                delete_link(backlink_action_hash.clone())?;
-                links_deleted.push(NodeLink {
-                    src: link_tag_content.target_node_id,
-                    dst: NodeId::Thing(input.thing_id.clone()),
-                    tag: link_tag_content.tag,
-                    create_action_hash: backlink_action_hash,
-                });
-            }
-        }
+        links_to_delete.extend(find_links_connected_to_thing(&input.thing_id)?);
+        links_to_delete.extend(query_my_links_to_thing(&input.thing_id)?);
     }
 
     // 3. Delete all links from the creator to the Thing
     if input.delete_links_from_creator {
         let creator = thing_record.action().author();
         let links_from_creator =
-            get_links(GetLinksInputBuilder::try_new(creator.clone(), LinkTypes::ToAgent)?.build())?;
+            get_links(GetLinksInputBuilder::try_new(creator.clone(), LinkTypes::ToThing)?.build())?;
         for link in links_from_creator {
-            let link_tag_content = deserialize_link_tag(link.tag.0)?;
             if link.target == input.thing_id.clone().into() {
-                delete_link(link.create_link_hash.clone())?;
-                links_deleted.push(NodeLink {
-                    src: NodeId::Agent(creator.clone()),
-                    dst: NodeId::Thing(input.thing_id.clone()),
-                    tag: link_tag_content.tag,
-                    create_action_hash: link.create_link_hash,
-                });
+                links_to_delete.push(link.create_link_hash);
             }
         }
     }
 
-    // 3. Delete all links that are passed explicitly in the input
-    // let all_to_links = get_links(input)
+    // The same link may have been found in multiple ways
+    let mut deleted: HashSet<ActionHash> = HashSet::new();
+    for link_action_hash in links_to_delete {
+        if deleted.insert(link_action_hash.clone()) {
+            delete_link(link_action_hash)?;
+        }
+    }
+
+    // 4. Delete all links that are passed explicitly in the input
     if let Some(delete_links) = input.delete_links {
-        let mut deleted_links = delete_links_from_node_inner(CreateOrDeleteLinksInput {
+        delete_links_from_node_inner(CreateOrDeleteLinksInput {
             src: NodeId::Thing(input.thing_id.clone()),
             links: delete_links,
         })?;
-        links_deleted.append(&mut deleted_links);
     }
 
-    // 4. Emit signals to the frontend
-    emit_signal(Signal::Local(SignalKind::ThingDeleted {
-        id: input.thing_id.clone(),
-    }))?;
-    emit_signal(Signal::Local(SignalKind::LinksDeleted {
-        links: links_deleted,
-    }))?;
-
+    // Signals to the frontend are emitted in post_commit
     Ok(())
 }
 
-#[hdk_extern]
-pub fn get_all_linked_node_ids(node_id: NodeId) -> ExternResult<Vec<NodeIdAndMetaTag>> {
-    let mut linked_node_ids: Vec<NodeIdAndMetaTag> = Vec::new();
-    let linked_thing_ids = get_linked_thing_ids(node_id.clone())?;
-    for (thing_id, meta_tag) in linked_thing_ids {
-        let node = NodeId::Thing(thing_id);
-        linked_node_ids.push(NodeIdAndMetaTag {
-            node_id: node,
-            meta_tag,
-        });
+/// Finds the links connecting a Thing with other nodes that can be found
+/// from the Thing itself: the backlinks of its bidirectional links, the
+/// links created in the From direction, which are stored at the Thing and
+/// name it as their target node, and the bidirectional links pointing to
+/// the Thing together with their backlinks stored at it. Other links stored
+/// at the Thing point away from it and are left out.
+fn find_links_connected_to_thing(thing_id: &ActionHash) -> ExternResult<Vec<ActionHash>> {
+    let thing_node = NodeId::Thing(thing_id.clone());
+    let links = get_links_many(
+        [LinkTypes::ToAgent, LinkTypes::ToThing, LinkTypes::ToAnchor]
+            .into_iter()
+            .map(
+                |link_type| Ok(GetLinksInputBuilder::try_new(thing_id.clone(), link_type)?.build()),
+            )
+            .collect::<ExternResult<Vec<GetLinksInput>>>()?,
+    )?;
+    let mut link_action_hashes: Vec<ActionHash> = Vec::new();
+    let mut possible_backlinks: HashSet<ActionHash> = HashSet::new();
+    let mut counterpart_bases: Vec<AnyLinkableHash> = Vec::new();
+    for link in links.into_iter().flatten() {
+        let Ok(link_tag_content) = deserialize_link_tag(link.tag.0.clone()) else {
+            continue;
+        };
+        match link_tag_content.backlink_action_hash {
+            Some(backlink_action_hash) => link_action_hashes.push(backlink_action_hash),
+            None if link_tag_content.target_node_id == thing_node => {
+                link_action_hashes.push(link.create_link_hash)
+            }
+            None => {
+                possible_backlinks.insert(link.create_link_hash);
+                if !counterpart_bases.contains(&link.target) {
+                    counterpart_bases.push(link.target);
+                }
+            }
+        }
     }
-    let linked_anchors = get_linked_anchors(node_id.clone())?;
-    for (anchor, meta_tag) in linked_anchors {
-        let node = NodeId::Anchor(anchor);
-        linked_node_ids.push(NodeIdAndMetaTag {
-            node_id: node,
-            meta_tag,
-        });
+
+    // The links pointing to the Thing from the other end of the possible
+    // backlinks that name one of them as their backlink
+    let counterparts = get_links_many(
+        counterpart_bases
+            .into_iter()
+            .map(|base| Ok(GetLinksInputBuilder::try_new(base, LinkTypes::ToThing)?.build()))
+            .collect::<ExternResult<Vec<GetLinksInput>>>()?,
+    )?;
+    let thing_hash: AnyLinkableHash = thing_id.clone().into();
+    for link in counterparts.into_iter().flatten() {
+        if link.target != thing_hash {
+            continue;
+        }
+        let Ok(link_tag_content) = deserialize_link_tag(link.tag.0.clone()) else {
+            continue;
+        };
+        if let Some(backlink_action_hash) = link_tag_content.backlink_action_hash {
+            if possible_backlinks.contains(&backlink_action_hash) {
+                link_action_hashes.push(link.create_link_hash);
+                link_action_hashes.push(backlink_action_hash);
+            }
+        }
     }
-    let linked_agents = get_linked_agents(node_id)?;
-    for (agent, meta_tag) in linked_agents {
-        let node = NodeId::Agent(agent);
-        linked_node_ids.push(NodeIdAndMetaTag {
-            node_id: node,
-            meta_tag,
-        });
+    Ok(link_action_hashes)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GetLinkedNodesInput {
+    pub node_id: NodeId,
+    /// If provided, only links whose user tag starts with these bytes are fetched
+    pub tag_prefix: Option<Vec<u8>>,
+    /// If provided, only links created after this time are fetched
+    pub after: Option<Timestamp>,
+    /// If provided, only links created before this time are fetched
+    pub before: Option<Timestamp>,
+    /// If provided, only links created by this agent are fetched
+    pub author: Option<AgentPubKey>,
+    /// If provided, the results are sorted accordingly. Otherwise they are
+    /// returned in the order the links were returned by the DHT. Only
+    /// supported by get_linked_things and get_all_linked_nodes.
+    pub sort: Option<SortBy>,
+    /// Whether to fetch the links and linked nodes from the network or only
+    /// from the local databases. Defaults to Network.
+    pub get_strategy: Option<GetStrategy>,
+    /// Whether to return linked Things that have been moved to the trash.
+    /// Not supported by get_linked_agents and get_linked_anchors, which
+    /// never return Things.
+    pub include_trashed: Option<bool>,
+}
+
+impl From<NodeId> for GetLinkedNodesInput {
+    fn from(node_id: NodeId) -> Self {
+        GetLinkedNodesInput {
+            node_id,
+            tag_prefix: None,
+            after: None,
+            before: None,
+            author: None,
+            sort: None,
+            get_strategy: None,
+            include_trashed: None,
+        }
     }
-    Ok(linked_node_ids)
 }
 
-#[hdk_extern]
-pub fn get_all_linked_nodes(node_id: NodeId) -> ExternResult<Vec<NodeContent>> {
-    let mut linked_nodes: Vec<NodeContent> = Vec::new();
-    let linked_things = get_linked_things(node_id.clone())?;
-    for thing in linked_things {
-        let node = NodeContent::Thing(thing);
-        linked_nodes.push(node);
+/// Leaves out the links to Things that have been moved to the trash unless
+/// include_trashed is set
+fn exclude_trashed_links(
+    links: Vec<(Link, LinkTagContent)>,
+    input: &GetLinkedNodesInput,
+) -> ExternResult<Vec<(Link, LinkTagContent)>> {
+    match input.include_trashed.unwrap_or(false) {
+        true => Ok(links),
+        false => without_trashed_links(links, input.get_strategy.unwrap_or(GetStrategy::Network)),
     }
-    let linked_anchors = get_linked_anchors(node_id.clone())?;
-    for (anchor, _) in linked_anchors {
-        let node = NodeContent::Anchor(anchor);
-        linked_nodes.push(node);
+}
+
+#[hdk_extern]
+pub fn get_all_linked_node_ids(
+    input: ReadInput<NodeId, GetLinkedNodesInput>,
+) -> ExternResult<Vec<NodeIdAndMetaTag>> {
+    let input = input.into_input();
+    reject_unused_options("get_all_linked_node_ids", &[("sort", input.sort.is_some())])?;
+    let links = exclude_trashed_links(get_all_links_from_node(&input)?, &input)?;
+    Ok(links_to_node_ids_and_meta_tags(links))
+}
+
+fn links_to_node_ids_and_meta_tags(links: Vec<(Link, LinkTagContent)>) -> Vec<NodeIdAndMetaTag> {
+    links
+        .into_iter()
+        .filter_map(|(link, meta_tag)| {
+            node_id_from_link(link, &meta_tag).map(|node_id| NodeIdAndMetaTag { node_id, meta_tag })
+        })
+        .collect()
+}
+
+#[hdk_extern]
+pub fn get_all_linked_nodes(
+    input: ReadInput<NodeId, GetLinkedNodesInput>,
+) -> ExternResult<Vec<NodeContent>> {
+    let input = input.into_input();
+    let links: Vec<(Link, LinkTagContent, NodeId)> =
+        exclude_trashed_links(get_all_links_from_node(&input)?, &input)?
+            .into_iter()
+            .filter_map(|(link, c)| node_id_from_link(link.clone(), &c).map(|n| (link, c, n)))
+            .collect();
+    let contents = get_node_contents(
+        links.iter().map(|(_, _, n)| n.clone()).collect(),
+        input.get_strategy.unwrap_or(GetStrategy::Network),
+    )?;
+    let mut linked_nodes: Vec<SortableLinkedNode<NodeContent>> = Vec::new();
+    for ((link, link_tag_content, _), content) in links.into_iter().zip(contents) {
+        let Some(node) = content else {
+            continue;
+        };
+        let thing = match &node {
+            NodeContent::Thing(thing) => Some(thing.clone()),
+            _ => None,
+        };
+        linked_nodes.push(SortableLinkedNode {
+            link,
+            link_tag_content,
+            thing,
+            node,
+        });
     }
-    let linked_agents = get_linked_agents(node_id)?;
-    for (agent, _) in linked_agents {
-        let node = NodeContent::Agent(agent);
-        linked_nodes.push(node);
+    if let Some(sort_by) = &input.sort {
+        linked_nodes = sort_linked_nodes(linked_nodes, sort_by);
     }
-    Ok(linked_nodes)
+    Ok(linked_nodes.into_iter().map(|n| n.node).collect())
 }
 
 #[hdk_extern]
-pub fn get_linked_agents(node_id: NodeId) -> ExternResult<Vec<(AgentPubKey, LinkTagContent)>> {
-    let base = linkable_hash_from_node_id(node_id)?;
-    let links = get_links(GetLinksInputBuilder::try_new(base, LinkTypes::ToAgent)?.build())?;
+pub fn get_linked_agents(
+    input: ReadInput<NodeId, GetLinkedNodesInput>,
+) -> ExternResult<Vec<(AgentPubKey, LinkTagContent)>> {
+    let input = input.into_input();
+    reject_unused_options(
+        "get_linked_agents",
+        &[
+            ("sort", input.sort.is_some()),
+            ("include_trashed", input.include_trashed.is_some()),
+        ],
+    )?;
+    let links = get_links_from_node(&input, LinkTypes::ToAgent)?;
     Ok(links
         .into_iter()
-        .map(|l| {
-            (
-                l.target.into_agent_pub_key(),
-                deserialize_link_tag(l.tag.0).ok(),
-            )
-        })
-        .filter(|(maybe_agent, maybe_tag)| maybe_agent.is_some() && maybe_tag.is_some())
-        .map(|(agent, tag)| (agent.unwrap(), tag.unwrap()))
+        .filter_map(|(l, c)| l.target.into_agent_pub_key().map(|agent| (agent, c)))
         .collect())
 }
 
 #[hdk_extern]
-pub fn get_linked_anchors(node_id: NodeId) -> ExternResult<Vec<(String, LinkTagContent)>> {
-    let base = linkable_hash_from_node_id(node_id)?;
-    let links = get_links(GetLinksInputBuilder::try_new(base, LinkTypes::ToAnchor)?.build())?;
+pub fn get_linked_anchors(
+    input: ReadInput<NodeId, GetLinkedNodesInput>,
+) -> ExternResult<Vec<(String, LinkTagContent)>> {
+    let input = input.into_input();
+    reject_unused_options(
+        "get_linked_anchors",
+        &[
+            ("sort", input.sort.is_some()),
+            ("include_trashed", input.include_trashed.is_some()),
+        ],
+    )?;
+    let links = get_links_from_node(&input, LinkTypes::ToAnchor)?;
     Ok(links
         .into_iter()
-        .filter_map(|l| deserialize_link_tag(l.tag.0).ok())
-        .map(|c| (anchor_string_from_node_id(c.target_node_id.clone()), c))
-        .filter(|(maybe_anchor, _)| maybe_anchor.is_some())
-        .map(|(anchor, meta_tag)| (anchor.unwrap(), meta_tag))
+        .filter_map(|(_, c)| anchor_string_from_node_id(c.target_node_id.clone()).map(|a| (a, c)))
         .collect())
 }
 
 /// Returns the linked thing ids together with the link tag
 #[hdk_extern]
-pub fn get_linked_thing_ids(node_id: NodeId) -> ExternResult<Vec<(ActionHash, LinkTagContent)>> {
-    let base = linkable_hash_from_node_id(node_id)?;
-    let links = get_links(GetLinksInputBuilder::try_new(base, LinkTypes::ToThing)?.build())?;
+pub fn get_linked_thing_ids(
+    input: ReadInput<NodeId, GetLinkedNodesInput>,
+) -> ExternResult<Vec<(ActionHash, LinkTagContent)>> {
+    let input = input.into_input();
+    reject_unused_options("get_linked_thing_ids", &[("sort", input.sort.is_some())])?;
+    let links = exclude_trashed_links(get_links_from_node(&input, LinkTypes::ToThing)?, &input)?;
     Ok(links
         .into_iter()
-        .map(|l| {
-            (
-                l.target.into_action_hash(),
-                deserialize_link_tag(l.tag.0).ok(),
-            )
-        })
-        .filter(|(maybe_action, maybe_tag)| maybe_action.is_some() && maybe_tag.is_some())
-        .map(|(action, tag)| (action.unwrap(), tag.unwrap()))
+        .filter_map(|(l, c)| l.target.into_action_hash().map(|action| (action, c)))
         .collect())
 }
 
 #[hdk_extern]
-pub fn get_linked_things(node_id: NodeId) -> ExternResult<Vec<Thing>> {
-    let base = linkable_hash_from_node_id(node_id)?;
-    let links = get_links(GetLinksInputBuilder::try_new(base, LinkTypes::ToThing)?.build())?;
-    let mut latest_maybe_things: Vec<Option<Thing>> = Vec::new();
-    for link in links {
-        let maybe_thing_id = link.target.into_action_hash();
-        if let Some(thing_id) = maybe_thing_id {
-            let latest_thing = get_latest_thing(thing_id)?;
-            latest_maybe_things.push(latest_thing);
+pub fn get_linked_things(
+    input: ReadInput<NodeId, GetLinkedNodesInput>,
+) -> ExternResult<Vec<Thing>> {
+    let input = input.into_input();
+    let links: Vec<(Link, LinkTagContent, ActionHash)> =
+        exclude_trashed_links(get_links_from_node(&input, LinkTypes::ToThing)?, &input)?
+            .into_iter()
+            .filter_map(|(link, c)| link.target.clone().into_action_hash().map(|t| (link, c, t)))
+            .collect();
+    let things = get_latest_things_batched(
+        links.iter().map(|(_, _, t)| t.clone()).collect(),
+        input.get_strategy.unwrap_or(GetStrategy::Network),
+    )?;
+    let mut linked_things: Vec<SortableLinkedNode<Thing>> = Vec::new();
+    for ((link, link_tag_content, _), maybe_thing) in links.into_iter().zip(things) {
+        if let Some(thing) = maybe_thing {
+            linked_things.push(SortableLinkedNode {
+                link,
+                link_tag_content,
+                thing: Some(thing.clone()),
+                node: thing,
+            });
+        }
+    }
+    // Items of ordered collections are returned in their order by default
+    let sort_by = input.sort.clone().or_else(|| {
+        linked_things
+            .iter()
+            .any(|t| t.link_tag_content.position.is_some())
+            .then_some(SortBy {
+                key: SortKey::Position,
+                order: SortOrder::Ascending,
+            })
+    });
+    if let Some(sort_by) = &sort_by {
+        linked_things = sort_linked_nodes(linked_things, sort_by);
+    }
+    Ok(linked_things.into_iter().map(|t| t.node).collect())
+}
+
+/// Position in a list of links ordered by link timestamp and create link hash
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LinkCursor {
+    pub timestamp: Timestamp,
+    pub create_link_hash: ActionHash,
+}
+
+impl From<&Link> for LinkCursor {
+    fn from(link: &Link) -> Self {
+        LinkCursor {
+            timestamp: link.timestamp,
+            create_link_hash: link.create_link_hash.clone(),
         }
     }
-    Ok(latest_maybe_things.into_iter().filter_map(|r| r).collect())
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct NodeAndLinkedIds {
-    pub content: NodeContent,
-    pub linked_node_ids: Vec<NodeIdAndMetaTag>,
+#[serde(tag = "type")]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GetLinkedNodesPageInput {
+    pub query: GetLinkedNodesInput,
+    pub limit: usize,
+    /// Cursor of the last link of the previous page. If None, the first
+    /// page is returned.
+    pub cursor: Option<LinkCursor>,
+    pub order: SortOrder,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Cursor to pass to get the next page. None if this is the last page.
+    pub next_cursor: Option<LinkCursor>,
+}
+
+/// Returns a page of the latest versions of the Things linked from a node.
+/// Only the Things of the links in the page are fetched. Things that can't
+/// be found or are trashed are skipped and the page is filled up with the
+/// following links, so a page only has fewer than `limit` items if it is
+/// the last one.
+#[hdk_extern]
+pub fn get_linked_things_page(input: GetLinkedNodesPageInput) -> ExternResult<Page<Thing>> {
+    reject_unused_options(
+        "get_linked_things_page",
+        &[("sort", input.query.sort.is_some())],
+    )?;
+    let links = get_links_from_node(&page_query(&input), LinkTypes::ToThing)?;
+    let strategy = input.query.get_strategy.unwrap_or(GetStrategy::Network);
+    paginate_links(links, &input, |links| {
+        let thing_ids: Vec<Option<ActionHash>> = links
+            .iter()
+            .map(|(link, _)| link.target.clone().into_action_hash())
+            .collect();
+        let mut things =
+            get_latest_things_batched(thing_ids.iter().flatten().cloned().collect(), strategy)?
+                .into_iter();
+        Ok(thing_ids
+            .into_iter()
+            .map(|thing_id| thing_id.and_then(|_| things.next().flatten()))
+            .collect())
+    })
 }
 
+/// Returns a page of the node ids linked from a node, across all link types
 #[hdk_extern]
-pub fn get_node_and_linked_node_ids(node_id: NodeId) -> ExternResult<Option<NodeAndLinkedIds>> {
-    let maybe_node_content = match node_id.clone() {
-        NodeId::Agent(a) => Some(NodeContent::Agent(a)),
-        NodeId::Anchor(s) => Some(NodeContent::Anchor(s)),
-        NodeId::Thing(thing_id) => {
-            let thing = get_latest_thing(thing_id)?;
-            match thing {
-                Some(t) => Some(NodeContent::Thing(t)),
-                _ => None,
+pub fn get_all_linked_node_ids_page(
+    input: GetLinkedNodesPageInput,
+) -> ExternResult<Page<NodeIdAndMetaTag>> {
+    reject_unused_options(
+        "get_all_linked_node_ids_page",
+        &[("sort", input.query.sort.is_some())],
+    )?;
+    let links = get_all_links_from_node(&page_query(&input))?;
+    paginate_links(links, &input, |links| {
+        Ok(links
+            .iter()
+            .map(|(link, meta_tag)| {
+                node_id_from_link(link.clone(), meta_tag).map(|node_id| NodeIdAndMetaTag {
+                    node_id,
+                    meta_tag: meta_tag.clone(),
+                })
+            })
+            .collect())
+    })
+}
+
+/// Returns a page of the nodes linked from a node, across all link types.
+/// Like for `get_linked_things_page`, Things that can't be found are skipped.
+#[hdk_extern]
+pub fn get_all_linked_nodes_page(
+    input: GetLinkedNodesPageInput,
+) -> ExternResult<Page<NodeContent>> {
+    reject_unused_options(
+        "get_all_linked_nodes_page",
+        &[("sort", input.query.sort.is_some())],
+    )?;
+    let links = get_all_links_from_node(&page_query(&input))?;
+    let strategy = input.query.get_strategy.unwrap_or(GetStrategy::Network);
+    paginate_links(links, &input, |links| {
+        let node_ids: Vec<Option<NodeId>> = links
+            .iter()
+            .map(|(link, meta_tag)| node_id_from_link(link.clone(), meta_tag))
+            .collect();
+        let mut contents =
+            get_node_contents(node_ids.iter().flatten().cloned().collect(), strategy)?.into_iter();
+        Ok(node_ids
+            .into_iter()
+            .map(|node_id| node_id.and_then(|_| contents.next().flatten()))
+            .collect())
+    })
+}
+
+/// Gets the links of all node link types (ToThing, ToAnchor, ToAgent) from a node
+pub(crate) fn get_all_links_from_node(
+    input: &GetLinkedNodesInput,
+) -> ExternResult<Vec<(Link, LinkTagContent)>> {
+    Ok(get_all_links_from_nodes(std::slice::from_ref(input))?
+        .pop()
+        .unwrap_or_default())
+}
+
+/// Gets the links of all node link types from many nodes in a single host call
+fn get_all_links_from_nodes(
+    inputs: &[GetLinkedNodesInput],
+) -> ExternResult<Vec<Vec<(Link, LinkTagContent)>>> {
+    let link_types = [LinkTypes::ToThing, LinkTypes::ToAnchor, LinkTypes::ToAgent];
+    let queries: Vec<(&GetLinkedNodesInput, LinkTypes)> = inputs
+        .iter()
+        .flat_map(|input| link_types.iter().map(move |link_type| (input, *link_type)))
+        .collect();
+    let links = get_links_from_nodes(&queries)?;
+    Ok(links
+        .chunks(link_types.len())
+        .map(|chunk| chunk.concat())
+        .collect())
+}
+
+/// The query of a page with its time range narrowed down to the links after
+/// the cursor (or before it for descending pages), so that the links of the
+/// previous pages aren't fetched again. The range is widened by a microsecond
+/// since links with the same timestamp as the cursor may still belong to the
+/// page; `paginate_links` compares their full cursors.
+fn page_query(input: &GetLinkedNodesPageInput) -> GetLinkedNodesInput {
+    let mut query = input.query.clone();
+    if let Some(cursor) = &input.cursor {
+        let micros = cursor.timestamp.as_micros();
+        match input.order {
+            SortOrder::Ascending => {
+                let after = Timestamp::from_micros(micros.saturating_sub(1));
+                query.after = Some(query.after.map_or(after, |a| a.max(after)));
+            }
+            SortOrder::Descending => {
+                let before = Timestamp::from_micros(micros.saturating_add(1));
+                query.before = Some(query.before.map_or(before, |b| b.min(before)));
             }
         }
+    }
+    query
+}
+
+/// Orders the links by their cursor and turns the ones after the cursor into
+/// the items of the requested page. Trashed links and links for which
+/// `to_items` returns None are skipped before counting towards the limit,
+/// so the following links are converted in batches until the page is full.
+fn paginate_links<T>(
+    mut links: Vec<(Link, LinkTagContent)>,
+    input: &GetLinkedNodesPageInput,
+    mut to_items: impl FnMut(&[(Link, LinkTagContent)]) -> ExternResult<Vec<Option<T>>>,
+) -> ExternResult<Page<T>> {
+    links.sort_by_key(|(link, _)| LinkCursor::from(link));
+    if let SortOrder::Descending = input.order {
+        links.reverse();
+    }
+    let mut remaining = links
+        .into_iter()
+        .filter(|(link, _)| match (&input.cursor, &input.order) {
+            (None, _) => true,
+            (Some(cursor), SortOrder::Ascending) => &LinkCursor::from(link) > cursor,
+            (Some(cursor), SortOrder::Descending) => &LinkCursor::from(link) < cursor,
+        })
+        .peekable();
+    let mut items: Vec<T> = Vec::new();
+    let mut last_cursor: Option<LinkCursor> = None;
+    while items.len() < input.limit && remaining.peek().is_some() {
+        let batch: Vec<(Link, LinkTagContent)> =
+            remaining.by_ref().take(input.limit - items.len()).collect();
+        last_cursor = batch.last().map(|(link, _)| LinkCursor::from(link));
+        let batch = exclude_trashed_links(batch, &input.query)?;
+        items.extend(to_items(&batch)?.into_iter().flatten());
+    }
+    let next_cursor = match remaining.peek() {
+        Some(_) => last_cursor,
+        None => None,
     };
-    if let Some(content) = maybe_node_content {
-        let linked_node_ids = get_all_linked_node_ids(node_id)?;
-        return Ok(Some(NodeAndLinkedIds {
-            content,
-            linked_node_ids,
-        }));
+    Ok(Page { items, next_cursor })
+}
+
+/// Derives the id of the node a link is pointing to from the link target or,
+/// for anchors, from the link tag
+pub(crate) fn node_id_from_link(link: Link, link_tag_content: &LinkTagContent) -> Option<NodeId> {
+    match LinkTypes::from_type(link.zome_index, link.link_type).ok()?? {
+        LinkTypes::ToThing => link.target.into_action_hash().map(NodeId::Thing),
+        LinkTypes::ToAgent => link.target.into_agent_pub_key().map(NodeId::Agent),
+        LinkTypes::ToAnchor => {
+            anchor_string_from_node_id(link_tag_content.target_node_id.clone()).map(NodeId::Anchor)
+        }
+        LinkTypes::ThingUpdates => None,
+    }
+}
+
+/// Reconstructs the link that was created by a CreateLink action, e.g. as
+/// returned by get_link_details
+pub(crate) fn link_from_create_link_action(action: &SignedActionHashed) -> Option<Link> {
+    match action.action() {
+        Action::CreateLink(create_link) => Some(Link {
+            author: create_link.author.clone(),
+            base: create_link.base_address.clone(),
+            target: create_link.target_address.clone(),
+            timestamp: create_link.timestamp,
+            zome_index: create_link.zome_index,
+            link_type: create_link.link_type,
+            tag: create_link.tag.clone(),
+            create_link_hash: action.action_address().clone(),
+        }),
+        _ => None,
+    }
+}
+
+/// Gets the links of the given type from a node together with their decoded
+/// link tags. Links whose tag cannot be decoded are skipped.
+pub(crate) fn get_links_from_node(
+    input: &GetLinkedNodesInput,
+    link_type: LinkTypes,
+) -> ExternResult<Vec<(Link, LinkTagContent)>> {
+    Ok(get_links_from_nodes(&[(input, link_type)])?
+        .pop()
+        .unwrap_or_default())
+}
+
+/// Like `get_links_from_node` for many queries, which are all sent to the
+/// host in a single call. Expired links are left out, and so are the links
+/// with a reserved tag unless they are explicitly asked for with that tag
+/// as tag_prefix.
+///
+/// Links with a version 0 tag don't start with their user tag, so for
+/// queries with a tag_prefix they are fetched with a second get_links input
+/// and then filtered by their decoded user tag like all other links.
+pub(crate) fn get_links_from_nodes(
+    queries: &[(&GetLinkedNodesInput, LinkTypes)],
+) -> ExternResult<Vec<Vec<(Link, LinkTagContent)>>> {
+    let mut get_links_inputs: Vec<GetLinksInput> = Vec::new();
+    let mut query_indices: Vec<usize> = Vec::new();
+    for (i, (input, link_type)) in queries.iter().enumerate() {
+        get_links_inputs.push(get_links_input(
+            input,
+            *link_type,
+            input.tag_prefix.as_deref(),
+        )?);
+        query_indices.push(i);
+        if input.tag_prefix.is_some() {
+            get_links_inputs.push(get_links_input(
+                input,
+                *link_type,
+                Some(LINK_TAG_V0_PREFIX),
+            )?);
+            query_indices.push(i);
+        }
+    }
+    let mut links: Vec<Vec<Link>> = vec![Vec::new(); queries.len()];
+    // A link with a newer tag may be returned for both inputs of a query if
+    // its user tag happens to start with the version 0 prefix
+    let mut seen: HashSet<(usize, ActionHash)> = HashSet::new();
+    for (i, query_links) in query_indices
+        .into_iter()
+        .zip(get_links_many(get_links_inputs)?)
+    {
+        for link in query_links {
+            if seen.insert((i, link.create_link_hash.clone())) {
+                links[i].push(link);
+            }
+        }
+    }
+    let now = sys_time()?;
+    Ok(queries
+        .iter()
+        .zip(links)
+        .map(|((input, _), links)| {
+            links
+                .into_iter()
+                .filter_map(|l| {
+                    let link_tag_content = deserialize_link_tag(l.tag.0.clone()).ok()?;
+                    Some((l, link_tag_content))
+                })
+                .filter(|(_, c)| !is_expired(c.expires_at, now))
+                .filter(|(_, c)| !is_hidden_link(c, input.tag_prefix.as_deref()))
+                .filter(|(_, c)| match &input.tag_prefix {
+                    Some(tag_prefix) => link_tag_has_prefix(c, tag_prefix),
+                    None => true,
+                })
+                .collect()
+        })
+        .collect())
+}
+
+/// The user tags of the links that the zome creates for its own bookkeeping,
+/// i.e. the subscriptions of agents and the links of trashed Things to the
+/// trash anchors, per link type they are used with
+fn reserved_tags(link_type: LinkTypes) -> &'static [&'static [u8]] {
+    match link_type {
+        LinkTypes::ToAgent => &[SUBSCRIBER_TAG],
+        LinkTypes::ToThing | LinkTypes::ToAnchor => &[TRASHED_TAG],
+        LinkTypes::ThingUpdates => &[],
+    }
+}
+
+/// Whether a link has one of the reserved tags and is therefore hidden from
+/// reads, unless that tag is the tag_prefix of the read
+pub(crate) fn is_hidden_link(link_tag_content: &LinkTagContent, tag_prefix: Option<&[u8]>) -> bool {
+    match link_tag_content.tag.as_deref() {
+        Some(tag @ (SUBSCRIBER_TAG | TRASHED_TAG)) => tag_prefix != Some(tag),
+        _ => false,
+    }
+}
+
+fn get_links_input(
+    input: &GetLinkedNodesInput,
+    link_type: LinkTypes,
+    raw_tag_prefix: Option<&[u8]>,
+) -> ExternResult<GetLinksInput> {
+    let base = linkable_hash_from_node_id(input.node_id.clone())?;
+    let mut links_input_builder = GetLinksInputBuilder::try_new(base, link_type)?;
+    if let Some(raw_tag_prefix) = raw_tag_prefix {
+        links_input_builder = links_input_builder.tag_prefix(LinkTag::new(raw_tag_prefix));
+    }
+    if let Some(after) = input.after {
+        links_input_builder = links_input_builder.after(after);
+    }
+    if let Some(before) = input.before {
+        links_input_builder = links_input_builder.before(before);
+    }
+    if let Some(author) = input.author.clone() {
+        links_input_builder = links_input_builder.author(author);
+    }
+    if let Some(get_strategy) = input.get_strategy {
+        links_input_builder = links_input_builder.get_options(get_strategy);
+    }
+    Ok(links_input_builder.build())
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NodeAndLinkedIds {
+    pub content: NodeContent,
+    pub linked_node_ids: Vec<NodeIdAndMetaTag>,
+    /// The hydrated linked nodes if `expand` was requested
+    pub expanded: Option<Vec<ExpandedNode>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ExpandInput {
+    /// How many hops of linked nodes to hydrate. 1 returns the content of
+    /// the directly linked nodes, 2 also of the nodes linked from them etc.
+    pub depth: usize,
+    /// If provided, only links whose user tag starts with these bytes are
+    /// followed
+    pub tag_prefix: Option<Vec<u8>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ExpandedNode {
+    pub node_id: NodeId,
+    pub meta_tag: LinkTagContent,
+    pub content: NodeContent,
+    /// The hydrated nodes linked from this node. Empty at the maximum depth
+    /// and for nodes that have already been expanded elsewhere in the tree.
+    pub linked_nodes: Vec<ExpandedNode>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GetNodeAndLinkedNodeIdsInput {
+    pub node_id: NodeId,
+    pub expand: Option<ExpandInput>,
+    /// Whether to fetch from the network or only from the local databases.
+    /// Defaults to Network.
+    pub get_strategy: Option<GetStrategy>,
+    /// Whether to return the node, linked nodes and expanded nodes that are
+    /// Things that have been moved to the trash
+    pub include_trashed: Option<bool>,
+}
+
+impl From<NodeId> for GetNodeAndLinkedNodeIdsInput {
+    fn from(node_id: NodeId) -> Self {
+        GetNodeAndLinkedNodeIdsInput {
+            node_id,
+            expand: None,
+            get_strategy: None,
+            include_trashed: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BatchGetNodeAndLinkedNodeIdsInput {
+    pub node_ids: Vec<NodeId>,
+    pub expand: Option<ExpandInput>,
+    /// Defaults to Network
+    pub get_strategy: Option<GetStrategy>,
+    /// Whether to return the nodes, linked nodes and expanded nodes that are
+    /// Things that have been moved to the trash
+    pub include_trashed: Option<bool>,
+}
+
+impl From<Vec<NodeId>> for BatchGetNodeAndLinkedNodeIdsInput {
+    fn from(node_ids: Vec<NodeId>) -> Self {
+        BatchGetNodeAndLinkedNodeIdsInput {
+            node_ids,
+            expand: None,
+            get_strategy: None,
+            include_trashed: None,
+        }
     }
-    Ok(None)
 }
 
+#[hdk_extern]
+pub fn get_node_and_linked_node_ids(
+    input: ReadInput<NodeId, GetNodeAndLinkedNodeIdsInput>,
+) -> ExternResult<Option<NodeAndLinkedIds>> {
+    let input = input.into_input();
+    Ok(batch_get_node_and_linked_node_ids(ReadInput::WithOptions(
+        BatchGetNodeAndLinkedNodeIdsInput {
+            node_ids: vec![input.node_id],
+            expand: input.expand,
+            get_strategy: input.get_strategy,
+            include_trashed: input.include_trashed,
+        },
+    ))?
+    .pop())
+}
+
+/// Fetches the contents of all nodes in one batch and then the links of all
+/// found nodes in another
 #[hdk_extern]
 pub fn batch_get_node_and_linked_node_ids(
-    nodes: Vec<NodeId>,
+    input: ReadInput<Vec<NodeId>, BatchGetNodeAndLinkedNodeIdsInput>,
 ) -> ExternResult<Vec<NodeAndLinkedIds>> {
+    let input = input.into_input();
+    let strategy = input.get_strategy.unwrap_or(GetStrategy::Network);
+    let include_trashed = input.include_trashed.unwrap_or(false);
+    let contents = get_node_contents(input.node_ids.clone(), strategy)?;
+    let mut found_nodes: Vec<(NodeId, NodeContent)> = input
+        .node_ids
+        .into_iter()
+        .zip(contents)
+        .filter_map(|(node_id, content)| content.map(|c| (node_id, c)))
+        .collect();
+    if !include_trashed {
+        found_nodes = without_trashed_nodes(found_nodes, strategy)?;
+    }
+    let link_inputs: Vec<GetLinkedNodesInput> = found_nodes
+        .iter()
+        .map(|(node_id, _)| GetLinkedNodesInput {
+            get_strategy: Some(strategy),
+            include_trashed: Some(include_trashed),
+            ..node_id.clone().into()
+        })
+        .collect();
+    let links = get_all_links_from_nodes(&link_inputs)?;
     let mut result: Vec<NodeAndLinkedIds> = Vec::new();
-    for node_id in nodes {
-        let maybe_node_content = match node_id.clone() {
-            NodeId::Agent(a) => Some(NodeContent::Agent(a)),
-            NodeId::Anchor(s) => Some(NodeContent::Anchor(s)),
-            NodeId::Thing(thing_id) => {
-                let thing = get_latest_thing(thing_id)?;
-                match thing {
-                    Some(t) => Some(NodeContent::Thing(t)),
-                    _ => None,
+    for (((node_id, content), links), link_input) in
+        found_nodes.into_iter().zip(links).zip(link_inputs.iter())
+    {
+        let links = exclude_trashed_links(links, link_input)?;
+        let expanded = match &input.expand {
+            Some(expand) => Some(expand_node(node_id, expand, strategy, include_trashed)?),
+            None => None,
+        };
+        result.push(NodeAndLinkedIds {
+            content,
+            linked_node_ids: links_to_node_ids_and_meta_tags(links),
+            expanded,
+        });
+    }
+    Ok(result)
+}
+
+/// Hydrates the nodes linked from a node down to the given depth. The tree
+/// is built breadth-first, one level per batch of host calls, and every
+/// node is only expanded where it first appears, i.e. at the lowest depth
+/// it appears at. This also keeps the expansion from running in circles.
+fn expand_node(
+    node_id: NodeId,
+    expand: &ExpandInput,
+    strategy: GetStrategy,
+    include_trashed: bool,
+) -> ExternResult<Vec<ExpandedNode>> {
+    let mut visited = HashSet::from([node_id.clone()]);
+    // The expanded nodes in breadth-first order, each with the index of its
+    // parent. None for the nodes linked from the start node.
+    let mut tree: Vec<(ExpandedNode, Option<usize>)> = Vec::new();
+    let mut level: Vec<(NodeId, Option<usize>)> = vec![(node_id, None)];
+    for _ in 0..expand.depth {
+        if level.is_empty() {
+            break;
+        }
+        let links_inputs: Vec<GetLinkedNodesInput> = level
+            .iter()
+            .map(|(node_id, _)| GetLinkedNodesInput {
+                tag_prefix: expand.tag_prefix.clone(),
+                get_strategy: Some(strategy),
+                include_trashed: Some(include_trashed),
+                ..node_id.clone().into()
+            })
+            .collect();
+        let links = get_all_links_from_nodes(&links_inputs)?;
+        let mut linked_nodes: Vec<(Option<usize>, LinkTagContent, NodeId)> = Vec::new();
+        for (((_, parent), links_input), links) in level.iter().zip(links_inputs.iter()).zip(links)
+        {
+            for (link, link_tag_content) in exclude_trashed_links(links, links_input)? {
+                if let Some(linked_node_id) = node_id_from_link(link, &link_tag_content) {
+                    linked_nodes.push((*parent, link_tag_content, linked_node_id));
                 }
             }
-        };
-        if let Some(content) = maybe_node_content {
-            let linked_node_ids = get_all_linked_node_ids(node_id)?;
-            result.push(NodeAndLinkedIds {
-                content,
-                linked_node_ids,
-            });
         }
+        let contents = get_node_contents(
+            linked_nodes.iter().map(|(_, _, n)| n.clone()).collect(),
+            strategy,
+        )?;
+        let mut next_level = Vec::new();
+        for ((parent, meta_tag, linked_node_id), content) in linked_nodes.into_iter().zip(contents)
+        {
+            let Some(content) = content else {
+                continue;
+            };
+            if visited.insert(linked_node_id.clone()) {
+                next_level.push((linked_node_id.clone(), Some(tree.len())));
+            }
+            tree.push((
+                ExpandedNode {
+                    node_id: linked_node_id,
+                    meta_tag,
+                    content,
+                    linked_nodes: Vec::new(),
+                },
+                parent,
+            ));
+        }
+        level = next_level;
     }
-    Ok(result)
+
+    // Parents come before their children, so attaching the nodes from the
+    // back completes every node before it is attached itself
+    let mut expanded_nodes = Vec::new();
+    while let Some((mut node, parent)) = tree.pop() {
+        node.linked_nodes.reverse();
+        match parent {
+            Some(parent) => tree[parent].0.linked_nodes.push(node),
+            None => expanded_nodes.push(node),
+        }
+    }
+    expanded_nodes.reverse();
+    Ok(expanded_nodes)
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -541,36 +1345,18 @@ pub struct CreateOrDeleteLinksInput {
 
 #[hdk_extern]
 pub fn create_links_from_node(input: CreateOrDeleteLinksInput) -> ExternResult<()> {
-    let mut links_created: Vec<NodeLinkMeta> = Vec::new();
     for link in input.links {
-        let (node_link, maybe_backlink) =
-            create_link_from_node_by_id(input.src.clone(), link.clone())?;
-        links_created.push(node_link);
-        if let Some(backlink) = maybe_backlink {
-            links_created.push(backlink);
-        }
+        create_link_from_node_by_id(input.src.clone(), link)?;
     }
-    emit_signal(Signal::Local(SignalKind::LinksCreated {
-        links: links_created,
-    }))?;
     Ok(())
 }
 
 #[hdk_extern]
 pub fn delete_links_from_node(input: CreateOrDeleteLinksInput) -> ExternResult<()> {
-    let links_deleted = delete_links_from_node_inner(input)?;
-
-    // Emit signals about deleted links to the frontend
-    emit_signal(Signal::Local(SignalKind::LinksDeleted {
-        links: links_deleted,
-    }))?;
-
-    Ok(())
+    delete_links_from_node_inner(input)
 }
 
-fn delete_links_from_node_inner(input: CreateOrDeleteLinksInput) -> ExternResult<Vec<NodeLink>> {
-    let mut links_deleted: Vec<NodeLink> = Vec::new();
-
+fn delete_links_from_node_inner(input: CreateOrDeleteLinksInput) -> ExternResult<()> {
     // Discern between "From" links and "To" or "Bidirectional" links
     let from_links = input
         .links
@@ -612,36 +1398,33 @@ fn delete_links_from_node_inner(input: CreateOrDeleteLinksInput) -> ExternResult
     let anchor_link_inputs = to_or_bidirectional_links
         .clone()
         .into_iter()
-        .map(|l| match l.node_id {
+        .filter_map(|l| match l.node_id {
             NodeId::Anchor(_) => Some(l),
             _ => None,
         })
-        .filter_map(|l| l)
         .collect::<Vec<LinkInput>>();
 
     let agent_link_inputs = to_or_bidirectional_links
         .clone()
         .into_iter()
-        .map(|l| match l.node_id {
+        .filter_map(|l| match l.node_id {
             NodeId::Agent(_) => Some(l),
             _ => None,
         })
-        .filter_map(|l| l)
         .collect::<Vec<LinkInput>>();
 
     let thing_link_inputs = to_or_bidirectional_links
         .clone()
         .into_iter()
-        .map(|l| match l.node_id {
+        .filter_map(|l| match l.node_id {
             NodeId::Thing(_) => Some(l),
             _ => None,
         })
-        .filter_map(|l| l)
         .collect::<Vec<LinkInput>>();
 
     let base = linkable_hash_from_node_id(input.src.clone())?;
 
-    if anchor_link_inputs.len() > 0 {
+    if !anchor_link_inputs.is_empty() {
         for link_input in anchor_link_inputs {
             let links_to_anchors = get_links(
                 GetLinksInputBuilder::try_new(base.clone(), LinkTypes::ToAnchor)?.build(),
@@ -650,31 +1433,16 @@ fn delete_links_from_node_inner(input: CreateOrDeleteLinksInput) -> ExternResult
                 let target = linkable_hash_from_node_id(link_input.node_id.clone())?;
                 let link_tag_content = deserialize_link_tag(link.tag.0)?;
                 if target == link.target && link_input.tag == link_tag_content.tag {
-                    if let Some(backlink_action_hash) =
-                        link_tag_content.backlink_action_hash.clone()
-                    {
-                        delete_link(backlink_action_hash.clone())?;
-                        links_deleted.push(NodeLink {
-                            src: link_tag_content.target_node_id,
-                            // TODO
-                            dst: input.src.clone(),
-                            tag: link_tag_content.tag,
-                            create_action_hash: backlink_action_hash,
-                        });
+                    if let Some(backlink_action_hash) = link_tag_content.backlink_action_hash {
+                        delete_link(backlink_action_hash)?;
                     }
-                    delete_link(link.create_link_hash.clone())?;
-                    links_deleted.push(NodeLink {
-                        src: input.src.clone(),
-                        dst: link_input.node_id.clone(),
-                        tag: link_input.tag.clone(),
-                        create_action_hash: link.create_link_hash,
-                    });
+                    delete_link(link.create_link_hash)?;
                 }
             }
         }
     }
 
-    if agent_link_inputs.len() > 0 {
+    if !agent_link_inputs.is_empty() {
         for link_input in agent_link_inputs {
             let links_to_agents = get_links(
                 GetLinksInputBuilder::try_new(base.clone(), LinkTypes::ToAgent)?.build(),
@@ -684,27 +1452,15 @@ fn delete_links_from_node_inner(input: CreateOrDeleteLinksInput) -> ExternResult
                 let link_tag_content = deserialize_link_tag(link.tag.0)?;
                 if target == link.target && link_input.tag == link_tag_content.tag {
                     if let Some(backlink_action_hash) = link_tag_content.backlink_action_hash {
-                        delete_link(backlink_action_hash.clone())?;
-                        links_deleted.push(NodeLink {
-                            src: link_tag_content.target_node_id,
-                            dst: input.src.clone(),
-                            tag: link_tag_content.tag,
-                            create_action_hash: backlink_action_hash,
-                        });
+                        delete_link(backlink_action_hash)?;
                     }
-                    delete_link(link.create_link_hash.clone())?;
-                    links_deleted.push(NodeLink {
-                        src: input.src.clone(),
-                        dst: link_input.node_id.clone(),
-                        tag: link_input.tag.clone(),
-                        create_action_hash: link.create_link_hash,
-                    });
+                    delete_link(link.create_link_hash)?;
                 }
             }
         }
     }
 
-    if thing_link_inputs.len() > 0 {
+    if !thing_link_inputs.is_empty() {
         for link_input in thing_link_inputs {
             let links_to_things = get_links(
                 GetLinksInputBuilder::try_new(base.clone(), LinkTypes::ToThing)?.build(),
@@ -714,44 +1470,205 @@ fn delete_links_from_node_inner(input: CreateOrDeleteLinksInput) -> ExternResult
                 let link_tag_content = deserialize_link_tag(link.tag.0)?;
                 if target == link.target && link_input.tag == link_tag_content.tag {
                     if let Some(backlink_action_hash) = link_tag_content.backlink_action_hash {
-                        delete_link(backlink_action_hash.clone())?;
-                        links_deleted.push(NodeLink {
-                            src: link_tag_content.target_node_id,
-                            dst: input.src.clone(),
-                            tag: link_tag_content.tag,
-                            create_action_hash: backlink_action_hash,
-                        });
+                        delete_link(backlink_action_hash)?;
                     }
-                    delete_link(link.create_link_hash.clone())?;
-                    links_deleted.push(NodeLink {
-                        src: input.src.clone(),
-                        dst: link_input.node_id.clone(),
-                        tag: link_input.tag.clone(),
-                        create_action_hash: link.create_link_hash,
-                    });
+                    delete_link(link.create_link_hash)?;
                 }
             }
         }
     }
 
-    Ok(links_deleted)
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HasLinkInput {
+    pub src: NodeId,
+    pub dst: NodeId,
+    pub tag: Option<Vec<u8>>,
+}
+
+/// Checks whether a link with exactly the given tag exists from src to dst
+#[hdk_extern]
+pub fn has_link(input: HasLinkInput) -> ExternResult<bool> {
+    Ok(find_link_between_nodes(input.src, input.dst, input.tag)?.is_some())
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CountLinksInput {
+    pub node_id: NodeId,
+    /// If provided, only links of this type are counted. Must be one of
+    /// ToThing, ToAgent or ToAnchor.
+    pub link_type: Option<LinkTypes>,
+    /// If provided, only links whose user tag starts with these bytes are counted
+    pub tag_prefix: Option<Vec<u8>>,
+}
+
+/// Number of links from a node per link type. None for link types that
+/// were not counted.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct LinkCounts {
+    pub to_thing: Option<usize>,
+    pub to_agent: Option<usize>,
+    pub to_anchor: Option<usize>,
+}
+
+/// Counts the links from a node without fetching them. The counting is done
+/// by the DHT authorities on the raw link tags, so with a tag_prefix, links
+/// whose user tag is shorter than the prefix may be counted in rare cases
+/// where their metadata happens to continue with the remaining prefix bytes.
+/// Links with a version 0 tag can't be matched by a prefix on the DHT, so
+/// with a tag_prefix they are fetched and counted in the zome instead.
+/// Like get_links_from_nodes, the links to the subscribers of the node are
+/// only counted if the subscriber tag is given as tag_prefix. Unlike the
+/// reads however, the counts are raw: expired links and links to Things
+/// that have been moved to the trash are counted as well, since telling
+/// them apart would require fetching the links.
+#[hdk_extern]
+pub fn count_links(input: CountLinksInput) -> ExternResult<LinkCounts> {
+    let base = linkable_hash_from_node_id(input.node_id)?;
+    let count_with_prefix = |link_type: LinkTypes, tag_prefix: Option<&[u8]>| {
+        let mut query = LinkQuery::new(base.clone(), link_type.try_into_filter()?);
+        if let Some(tag_prefix) = tag_prefix {
+            query = query.tag_prefix(LinkTag::new(tag_prefix));
+        }
+        hdk::link::count_links(query)
+    };
+    let count = |link_type: LinkTypes| -> ExternResult<Option<usize>> {
+        let tag_prefix = input.tag_prefix.as_deref();
+        let count = count_with_prefix(link_type, tag_prefix)?
+            + match tag_prefix {
+                Some(tag_prefix) => count_v0_links_with_prefix(&base, link_type, tag_prefix)?,
+                None => 0,
+            };
+        // Links with a reserved tag are among the counted links if their tag
+        // starts with the prefix, in which case they are subtracted again
+        let mut hidden = 0;
+        for reserved_tag in reserved_tags(link_type) {
+            if tag_prefix != Some(*reserved_tag)
+                && reserved_tag.starts_with(tag_prefix.unwrap_or_default())
+            {
+                hidden += count_with_prefix(link_type, Some(*reserved_tag))?;
+            }
+        }
+        Ok(Some(count.saturating_sub(hidden)))
+    };
+    match input.link_type {
+        None => Ok(LinkCounts {
+            to_thing: count(LinkTypes::ToThing)?,
+            to_agent: count(LinkTypes::ToAgent)?,
+            to_anchor: count(LinkTypes::ToAnchor)?,
+        }),
+        Some(LinkTypes::ToThing) => Ok(LinkCounts {
+            to_thing: count(LinkTypes::ToThing)?,
+            ..Default::default()
+        }),
+        Some(LinkTypes::ToAgent) => Ok(LinkCounts {
+            to_agent: count(LinkTypes::ToAgent)?,
+            ..Default::default()
+        }),
+        Some(LinkTypes::ToAnchor) => Ok(LinkCounts {
+            to_anchor: count(LinkTypes::ToAnchor)?,
+            ..Default::default()
+        }),
+        Some(LinkTypes::ThingUpdates) => Err(wasm_error!(WasmErrorInner::Guest(
+            "Only ToThing, ToAgent and ToAnchor links can be counted.".into()
+        ))),
+    }
+}
+
+/// Counts the links with a version 0 tag whose user tag starts with the given
+/// prefix. Their user tag is not at the start of the raw tag, so they can't
+/// be counted by the DHT authorities and are fetched instead.
+fn count_v0_links_with_prefix(
+    base: &AnyLinkableHash,
+    link_type: LinkTypes,
+    tag_prefix: &[u8],
+) -> ExternResult<usize> {
+    let links = get_links(
+        GetLinksInputBuilder::try_new(base.clone(), link_type)?
+            .tag_prefix(LinkTag::new(LINK_TAG_V0_PREFIX))
+            .build(),
+    )?;
+    Ok(links
+        .into_iter()
+        .filter(|link| match VersionedLinkTag::decode(&link.tag.0) {
+            Ok(versioned_link_tag @ VersionedLinkTag::V0(_)) => {
+                link_tag_has_prefix(&versioned_link_tag.into(), tag_prefix)
+            }
+            _ => false,
+        })
+        .count())
+}
+
+/// Counts the links for multiple nodes in a single zome call
+#[hdk_extern]
+pub fn batch_count_links(inputs: Vec<CountLinksInput>) -> ExternResult<Vec<LinkCounts>> {
+    inputs.into_iter().map(count_links).collect()
 }
 
-fn create_link_from_node_by_id(
+/// Looks up an existing link from src to dst whose user tag matches the given tag
+fn find_link_between_nodes(
+    src: NodeId,
+    dst: NodeId,
+    tag: Option<Vec<u8>>,
+) -> ExternResult<Option<(ActionHash, LinkTagContent)>> {
+    let target = linkable_hash_from_node_id(dst.clone())?;
+    let links = get_links_from_node(
+        &GetLinkedNodesInput {
+            tag_prefix: tag.clone(),
+            ..src.into()
+        },
+        link_type_for_node_id(&dst),
+    )?;
+    Ok(links
+        .into_iter()
+        .find(|(link, link_tag_content)| link.target == target && link_tag_content.tag == tag)
+        .map(|(link, link_tag_content)| (link.create_link_hash, link_tag_content)))
+}
+
+pub(crate) fn create_link_from_node_by_id(
     src: NodeId,
     link: LinkInput,
 ) -> ExternResult<(NodeLinkMeta, Option<NodeLinkMeta>)> {
+    if link.dedupe {
+        let existing_link = match link.direction {
+            LinkDirection::From => {
+                find_link_between_nodes(link.node_id.clone(), src.clone(), link.tag.clone())?
+            }
+            _ => find_link_between_nodes(src.clone(), link.node_id.clone(), link.tag.clone())?,
+        };
+        if let Some((create_action_hash, meta_tag)) = existing_link {
+            return Ok((
+                NodeLinkMeta {
+                    src,
+                    dst: link.node_id,
+                    meta_tag,
+                    create_action_hash,
+                },
+                None,
+            ));
+        }
+    }
     let base: HoloHash<hash_type::AnyLinkable> = linkable_hash_from_node_id(src.clone())?;
-    let base_link_type = match src.clone() {
-        NodeId::Agent(_) => LinkTypes::ToAgent,
-        NodeId::Anchor(_) => LinkTypes::ToAnchor,
-        NodeId::Thing(_) => LinkTypes::ToThing,
-    };
+    // Anchors can't be derived from their path entry hash, so the base of a
+    // link is named in its tag if it's an anchor
+    let src_anchor = anchor_string_from_node_id(src.clone());
+    let dst_anchor = anchor_string_from_node_id(link.node_id.clone());
+    let base_link_type = link_type_for_node_id(&src);
     match link.node_id.clone() {
         NodeId::Agent(agent) => match link.direction {
             LinkDirection::To => {
-                let (link_tag, link_tag_content) =
-                    derive_link_tag(link.tag.clone(), None, link.node_id.clone(), None, None)?;
+                let (link_tag, link_tag_content) = derive_link_tag(
+                    link.tag.clone(),
+                    None,
+                    link.node_id.clone(),
+                    None,
+                    None,
+                    link.position.clone(),
+                    link.expires_at,
+                    src_anchor.clone(),
+                )?;
                 let ah = create_link(base.clone(), agent, LinkTypes::ToAgent, link_tag)?;
                 Ok((
                     NodeLinkMeta {
@@ -785,6 +1702,9 @@ fn create_link_from_node_by_id(
                     link.node_id.clone(),
                     src_thing_created_at,
                     src_thing_created_by,
+                    link.position.clone(),
+                    link.expires_at,
+                    dst_anchor.clone(),
                 )?;
                 let ah = create_link(agent, base.clone(), base_link_type, link_tag)?;
                 Ok((
@@ -819,6 +1739,9 @@ fn create_link_from_node_by_id(
                     src.clone(),
                     src_thing_created_at,
                     src_thing_created_by,
+                    None,
+                    link.expires_at,
+                    dst_anchor.clone(),
                 )?;
                 let backlink_action_hash = create_link(
                     agent.clone(),
@@ -832,6 +1755,9 @@ fn create_link_from_node_by_id(
                     link.node_id.clone(),
                     None,
                     None,
+                    link.position.clone(),
+                    link.expires_at,
+                    src_anchor.clone(),
                 )?;
 
                 let ah = create_link(base.clone(), agent, LinkTypes::ToAgent, link_tag)?;
@@ -856,8 +1782,16 @@ fn create_link_from_node_by_id(
             let path_entry_hash = path.path_entry_hash()?;
             match link.direction {
                 LinkDirection::To => {
-                    let (link_tag, link_tag_content) =
-                        derive_link_tag(link.tag.clone(), None, link.node_id.clone(), None, None)?;
+                    let (link_tag, link_tag_content) = derive_link_tag(
+                        link.tag.clone(),
+                        None,
+                        link.node_id.clone(),
+                        None,
+                        None,
+                        link.position.clone(),
+                        link.expires_at,
+                        src_anchor.clone(),
+                    )?;
                     let ah =
                         create_link(base.clone(), path_entry_hash, LinkTypes::ToAnchor, link_tag)?;
                     Ok((
@@ -892,6 +1826,9 @@ fn create_link_from_node_by_id(
                         link.node_id.clone(),
                         src_thing_created_at,
                         src_thing_created_by,
+                        link.position.clone(),
+                        link.expires_at,
+                        dst_anchor.clone(),
                     )?;
                     let ah = create_link(path_entry_hash, base.clone(), base_link_type, link_tag)?;
                     Ok((
@@ -926,6 +1863,9 @@ fn create_link_from_node_by_id(
                         src.clone(),
                         src_thing_created_at,
                         src_thing_created_by,
+                        None,
+                        link.expires_at,
+                        dst_anchor.clone(),
                     )?;
                     let backlink_action_hash = create_link(
                         path_entry_hash.clone(),
@@ -939,6 +1879,9 @@ fn create_link_from_node_by_id(
                         link.node_id.clone(),
                         None,
                         None,
+                        link.position.clone(),
+                        link.expires_at,
+                        src_anchor.clone(),
                     )?;
                     let ah =
                         create_link(base.clone(), path_entry_hash, LinkTypes::ToAnchor, link_tag)?;
@@ -974,6 +1917,9 @@ fn create_link_from_node_by_id(
                         link.node_id.clone(),
                         Some(thing_record.action().timestamp()),
                         Some(thing_record.action().author().clone()),
+                        link.position.clone(),
+                        link.expires_at,
+                        src_anchor.clone(),
                     )?;
                     let ah = create_link(
                         base.clone(),
@@ -1013,6 +1959,9 @@ fn create_link_from_node_by_id(
                         link.node_id.clone(),
                         src_thing_created_at,
                         src_thing_created_by,
+                        link.position.clone(),
+                        link.expires_at,
+                        dst_anchor.clone(),
                     )?;
                     let ah = create_link(action_hash, base.clone(), base_link_type, link_tag)?;
                     Ok((
@@ -1047,6 +1996,9 @@ fn create_link_from_node_by_id(
                         src.clone(),
                         src_thing_created_at,
                         src_thing_created_by,
+                        None,
+                        link.expires_at,
+                        dst_anchor.clone(),
                     )?;
                     let backlink_action_hash = create_link(
                         action_hash.clone(),
@@ -1060,6 +2012,9 @@ fn create_link_from_node_by_id(
                         link.node_id.clone(),
                         Some(thing_record.action().timestamp()),
                         Some(thing_record.action().author().clone()),
+                        link.position.clone(),
+                        link.expires_at,
+                        src_anchor.clone(),
                     )?;
                     let ah = create_link(base.clone(), action_hash, LinkTypes::ToThing, link_tag)?;
                     Ok((
@@ -1082,7 +2037,7 @@ fn create_link_from_node_by_id(
     }
 }
 
-fn linkable_hash_from_node_id(node_id: NodeId) -> ExternResult<AnyLinkableHash> {
+pub(crate) fn linkable_hash_from_node_id(node_id: NodeId) -> ExternResult<AnyLinkableHash> {
     match node_id {
         NodeId::Agent(a) => Ok(a.into()),
         NodeId::Anchor(a) => Ok(Path::from(a).path_entry_hash()?.into()),
@@ -1090,7 +2045,15 @@ fn linkable_hash_from_node_id(node_id: NodeId) -> ExternResult<AnyLinkableHash>
     }
 }
 
-fn thing_record_to_thing(record: Record, original_thing: Thing) -> ExternResult<Thing> {
+pub(crate) fn link_type_for_node_id(node_id: &NodeId) -> LinkTypes {
+    match node_id {
+        NodeId::Agent(_) => LinkTypes::ToAgent,
+        NodeId::Anchor(_) => LinkTypes::ToAnchor,
+        NodeId::Thing(_) => LinkTypes::ToThing,
+    }
+}
+
+pub(crate) fn thing_record_to_thing(record: Record, original_thing: Thing) -> ExternResult<Thing> {
     let thing_entry = record
     .entry()
     .to_app_option::<ThingEntry>()
@@ -1112,10 +2075,12 @@ fn thing_record_to_thing(record: Record, original_thing: Thing) -> ExternResult<
         creator: original_thing.creator,
         created_at: original_thing.created_at,
         updated_at,
+        deleted: original_thing.deleted,
+        expires_at: thing_entry.expires_at,
     })
 }
 
-fn original_thing_record_to_thing(record: Record) -> ExternResult<Thing> {
+pub(crate) fn original_thing_record_to_thing(record: Record) -> ExternResult<Thing> {
     let thing_entry = record
     .entry()
     .to_app_option::<ThingEntry>()
@@ -1133,6 +2098,8 @@ fn original_thing_record_to_thing(record: Record) -> ExternResult<Thing> {
         creator: record.action().author().clone(),
         created_at: record.action().timestamp(),
         updated_at: None,
+        deleted: None,
+        expires_at: thing_entry.expires_at,
     })
 }
 
@@ -0,0 +1,172 @@
+use crate::api::{original_thing_record_to_thing, thing_record_to_thing, NodeContent};
+use crate::expiry::is_expired;
+use crate::Thing;
+use generic_zome_integrity::*;
+use hdk::prelude::*;
+
+/// Gets the records of all hashes in a single host call
+pub(crate) fn get_many(
+    hashes: Vec<AnyDhtHash>,
+    strategy: GetStrategy,
+) -> ExternResult<Vec<Option<Record>>> {
+    if hashes.is_empty() {
+        return Ok(Vec::new());
+    }
+    let get_inputs = hashes
+        .into_iter()
+        .map(|hash| GetInput::new(hash, GetOptions { strategy }))
+        .collect();
+    HDK.with(|hdk| hdk.borrow().get(get_inputs))
+}
+
+/// Gets the links of all inputs in a single host call
+pub(crate) fn get_links_many(inputs: Vec<GetLinksInput>) -> ExternResult<Vec<Vec<Link>>> {
+    if inputs.is_empty() {
+        return Ok(Vec::new());
+    }
+    HDK.with(|hdk| hdk.borrow().get_links(inputs))
+}
+
+/// Gets the details of all hashes in a single host call
+pub(crate) fn get_details_many(
+    hashes: Vec<AnyDhtHash>,
+    strategy: GetStrategy,
+) -> ExternResult<Vec<Option<Details>>> {
+    if hashes.is_empty() {
+        return Ok(Vec::new());
+    }
+    let get_inputs = hashes
+        .into_iter()
+        .map(|hash| GetInput::new(hash, GetOptions { strategy }))
+        .collect();
+    HDK.with(|hdk| hdk.borrow().get_details(get_inputs))
+}
+
+/// Gets the link creates and deletes of all inputs in a single host call
+pub(crate) fn get_link_details_many(inputs: Vec<GetLinksInput>) -> ExternResult<Vec<LinkDetails>> {
+    if inputs.is_empty() {
+        return Ok(Vec::new());
+    }
+    HDK.with(|hdk| hdk.borrow().get_link_details(inputs))
+}
+
+/// Gets the latest known versions of many Things with one batch of host
+/// calls per stage: the original records, their update links and then the
+/// latest update records.
+pub(crate) fn get_latest_things_batched(
+    thing_ids: Vec<ActionHash>,
+    strategy: GetStrategy,
+) -> ExternResult<Vec<Option<Thing>>> {
+    let original_records = get_many(
+        thing_ids.iter().cloned().map(Into::into).collect(),
+        strategy,
+    )?;
+    let mut original_things: Vec<Option<(Record, Thing)>> = Vec::new();
+    for maybe_record in original_records {
+        original_things.push(match maybe_record {
+            Some(record) => Some((record.clone(), original_thing_record_to_thing(record)?)),
+            None => None,
+        });
+    }
+
+    let found_thing_ids: Vec<ActionHash> = thing_ids
+        .iter()
+        .zip(original_things.iter())
+        .filter(|(_, original)| original.is_some())
+        .map(|(thing_id, _)| thing_id.clone())
+        .collect();
+    let update_links = get_links_many(
+        found_thing_ids
+            .iter()
+            .map(|thing_id| {
+                Ok(
+                    GetLinksInputBuilder::try_new(thing_id.clone(), LinkTypes::ThingUpdates)?
+                        .get_options(strategy)
+                        .build(),
+                )
+            })
+            .collect::<ExternResult<Vec<GetLinksInput>>>()?,
+    )?;
+    let mut update_links = update_links.into_iter();
+
+    // Candidates for the latest update of each Thing, latest first
+    let mut candidates: Vec<Vec<ActionHash>> = original_things
+        .iter()
+        .map(|original| match original {
+            Some(_) => {
+                let mut links = update_links.next().unwrap_or_default();
+                links.sort_by_key(|l| std::cmp::Reverse(l.timestamp));
+                links
+                    .into_iter()
+                    .filter_map(|l| l.target.into_action_hash())
+                    .rev()
+                    .collect()
+            }
+            None => Vec::new(),
+        })
+        .collect();
+
+    // Fetch the latest candidate of all Things at once and retry with the
+    // next candidate for those whose record could not be found
+    let mut latest_records: Vec<Option<Record>> = vec![None; thing_ids.len()];
+    loop {
+        let pending: Vec<(usize, ActionHash)> = candidates
+            .iter_mut()
+            .enumerate()
+            .filter(|(i, _)| latest_records[*i].is_none())
+            .filter_map(|(i, c)| c.pop().map(|hash| (i, hash)))
+            .collect();
+        if pending.is_empty() {
+            break;
+        }
+        let records = get_many(
+            pending
+                .iter()
+                .map(|(_, hash)| hash.clone().into())
+                .collect(),
+            strategy,
+        )?;
+        for ((i, _), record) in pending.into_iter().zip(records) {
+            latest_records[i] = record;
+        }
+    }
+
+    // Expired Things are treated as absent
+    let now = sys_time()?;
+    original_things
+        .into_iter()
+        .zip(latest_records)
+        .map(|(original, latest_record)| match original {
+            Some((original_record, original_thing)) => Ok(Some(thing_record_to_thing(
+                latest_record.unwrap_or(original_record),
+                original_thing,
+            )?)
+            .filter(|thing| !is_expired(thing.expires_at, now))),
+            None => Ok(None),
+        })
+        .collect()
+}
+
+/// Gets the contents of many nodes, fetching all Things in one batch. None
+/// for Things that can't be found.
+pub(crate) fn get_node_contents(
+    node_ids: Vec<NodeId>,
+    strategy: GetStrategy,
+) -> ExternResult<Vec<Option<NodeContent>>> {
+    let thing_ids: Vec<ActionHash> = node_ids
+        .iter()
+        .filter_map(|node_id| match node_id {
+            NodeId::Thing(thing_id) => Some(thing_id.clone()),
+            _ => None,
+        })
+        .collect();
+    let mut things = get_latest_things_batched(thing_ids, strategy)?.into_iter();
+    Ok(node_ids
+        .into_iter()
+        .map(|node_id| match node_id {
+            NodeId::Agent(agent) => Some(NodeContent::Agent(agent)),
+            NodeId::Anchor(anchor) => Some(NodeContent::Anchor(anchor)),
+            NodeId::Thing(_) => things.next().flatten().map(NodeContent::Thing),
+        })
+        .collect())
+}
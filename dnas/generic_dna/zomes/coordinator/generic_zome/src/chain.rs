@@ -0,0 +1,70 @@
+use generic_zome_integrity::*;
+use hdk::prelude::*;
+
+/// Action hashes of all records the calling agent has deleted
+pub(crate) fn query_deleted_action_hashes() -> ExternResult<HashSet<ActionHash>> {
+    Ok(
+        query(ChainQueryFilter::new().action_type(ActionType::Delete))?
+            .into_iter()
+            .filter_map(|record| match record.action() {
+                Action::Delete(delete) => Some(delete.deletes_address.clone()),
+                _ => None,
+            })
+            .collect(),
+    )
+}
+
+/// Create action hashes of all links the calling agent has deleted
+pub(crate) fn query_deleted_link_action_hashes() -> ExternResult<HashSet<ActionHash>> {
+    Ok(
+        query(ChainQueryFilter::new().action_type(ActionType::DeleteLink))?
+            .into_iter()
+            .filter_map(|record| match record.action() {
+                Action::DeleteLink(delete_link) => Some(delete_link.link_add_address.clone()),
+                _ => None,
+            })
+            .collect(),
+    )
+}
+
+/// Records of all Things the calling agent has created and not deleted,
+/// oldest first
+pub(crate) fn query_my_live_things() -> ExternResult<Vec<Record>> {
+    let deleted = query_deleted_action_hashes()?;
+    let thing_entry_type: EntryType = UnitEntryTypes::Thing.try_into()?;
+    Ok(query(
+        ChainQueryFilter::new()
+            .entry_type(thing_entry_type)
+            .action_type(ActionType::Create)
+            .include_entries(true),
+    )?
+    .into_iter()
+    .filter(|record| !deleted.contains(record.action_address()))
+    .collect())
+}
+
+/// Create action hashes of the links to the given Thing that the calling
+/// agent has created and not deleted, including the ones that can't be
+/// found from the Thing since they have no backlink
+pub(crate) fn query_my_links_to_thing(thing_id: &ActionHash) -> ExternResult<Vec<ActionHash>> {
+    let deleted = query_deleted_link_action_hashes()?;
+    let target: AnyLinkableHash = thing_id.clone().into();
+    Ok(
+        query(ChainQueryFilter::new().action_type(ActionType::CreateLink))?
+            .into_iter()
+            .filter_map(|record| match record.action() {
+                Action::CreateLink(create_link)
+                    if create_link.target_address == target
+                        && matches!(
+                            LinkTypes::from_type(create_link.zome_index, create_link.link_type),
+                            Ok(Some(LinkTypes::ToThing))
+                        )
+                        && !deleted.contains(record.action_address()) =>
+                {
+                    Some(record.action_address().clone())
+                }
+                _ => None,
+            })
+            .collect(),
+    )
+}
@@ -0,0 +1,93 @@
+use crate::api::{delete_thing, DeleteThingInput};
+use crate::chain::{query_deleted_link_action_hashes, query_my_live_things};
+use generic_zome_integrity::*;
+use hdk::prelude::*;
+
+/// Crontab of the scheduled cleanup: every 10 minutes
+#[cfg(feature = "scheduled-cleanup")]
+const DELETE_EXPIRED_SCHEDULE: &str = "0 */10 * * * * *";
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct DeletedExpired {
+    /// Ids of the expired Things that have been deleted
+    pub thing_ids: Vec<ActionHash>,
+    /// Create action hashes of the expired links that have been deleted
+    pub link_action_hashes: Vec<ActionHash>,
+}
+
+/// Whether an item with the given expiry time is to be treated as absent at
+/// time `now`
+pub(crate) fn is_expired(expires_at: Option<Timestamp>, now: Timestamp) -> bool {
+    matches!(expires_at, Some(expires_at) if expires_at <= now)
+}
+
+/// Deletes all expired Things and links that the calling agent has authored
+/// and not deleted yet. Things are deleted together with their backlinks and
+/// the links from their creator. Reads already leave out expired items, so
+/// this only frees up the DHT and is safe to call at any time.
+#[hdk_extern]
+pub fn delete_expired(_: ()) -> ExternResult<DeletedExpired> {
+    let now = sys_time()?;
+    let deleted_links = query_deleted_link_action_hashes()?;
+
+    let mut result = DeletedExpired::default();
+
+    // 1. Delete the expired links, forward links and backlinks alike
+    let create_links = query(ChainQueryFilter::new().action_type(ActionType::CreateLink))?;
+    for record in create_links {
+        let Action::CreateLink(create_link) = record.action() else {
+            continue;
+        };
+        if deleted_links.contains(record.action_address()) {
+            continue;
+        }
+        // Only the node links of this zome have link tags to decode
+        if !matches!(
+            LinkTypes::from_type(create_link.zome_index, create_link.link_type),
+            Ok(Some(
+                LinkTypes::ToThing | LinkTypes::ToAgent | LinkTypes::ToAnchor
+            ))
+        ) {
+            continue;
+        }
+        let Ok(link_tag_content) = deserialize_link_tag(create_link.tag.0.clone()) else {
+            continue;
+        };
+        if is_expired(link_tag_content.expires_at, now) {
+            delete_link(record.action_address().clone())?;
+            result
+                .link_action_hashes
+                .push(record.action_address().clone());
+        }
+    }
+
+    // 2. Delete the expired Things
+    for record in query_my_live_things()? {
+        let Ok(Some(thing_entry)) = record.entry().to_app_option::<ThingEntry>() else {
+            continue;
+        };
+        if is_expired(thing_entry.expires_at, now) {
+            delete_thing(DeleteThingInput {
+                thing_id: record.action_address().clone(),
+                delete_backlinks: true,
+                delete_links_from_creator: true,
+                delete_links: None,
+            })?;
+            result.thing_ids.push(record.action_address().clone());
+        }
+    }
+
+    Ok(result)
+}
+
+/// Runs `delete_expired` periodically. Scheduled in `init` if the zome is
+/// built with the `scheduled-cleanup` feature, which requires a conductor
+/// with unstable functions enabled.
+#[cfg(feature = "scheduled-cleanup")]
+#[hdk_extern(infallible)]
+pub fn scheduled_delete_expired(_: Option<Schedule>) -> Option<Schedule> {
+    if let Err(err) = delete_expired(()) {
+        error!("Failed to delete expired items: {:?}", err);
+    }
+    Some(Schedule::Persisted(DELETE_EXPIRED_SCHEDULE.into()))
+}
@@ -0,0 +1,177 @@
+use crate::api::linkable_hash_from_node_id;
+use crate::batch::get_links_many;
+use crate::subscriptions::is_subscription_link;
+use crate::{Signal, SignalKind, SIMPLE_HOLOCHAIN_ALL_AGENTS};
+use generic_zome_integrity::*;
+use hdk::prelude::*;
+use std::collections::HashMap;
+
+/// Maximum number of agents a signal is sent to if not configured otherwise
+pub const DEFAULT_REMOTE_SIGNAL_FAN_OUT_LIMIT: usize = 50;
+
+/// DNA properties that configure the remote signals sent after each commit.
+/// All fields are optional, and null properties use the defaults. Other
+/// fields in the properties are ignored.
+#[derive(Serialize, Deserialize, SerializedBytes, Clone, Debug, Default)]
+pub struct RemoteSignalProperties {
+    /// Maximum number of agents each signal is sent to. Defaults to
+    /// DEFAULT_REMOTE_SIGNAL_FAN_OUT_LIMIT.
+    pub remote_signal_fan_out_limit: Option<usize>,
+    /// Whether to also send the signals to all agents linked from the
+    /// SIMPLE_HOLOCHAIN_ALL_AGENTS anchor. Defaults to false, so only the
+    /// agents interested in the touched nodes are signaled. Only enable it
+    /// in small networks, as every commit then fetches the links of that
+    /// anchor and signals up to the fan-out limit of agents.
+    pub remote_signal_all_agents: Option<bool>,
+}
+
+/// The msgpack encoding of null, which is what DNAs without properties have
+const NULL_PROPERTIES: &[u8] = &[0xc0];
+
+fn remote_signal_properties() -> ExternResult<RemoteSignalProperties> {
+    let properties = dna_info()?.modifiers.properties;
+    if properties.bytes().as_slice() == NULL_PROPERTIES {
+        return Ok(RemoteSignalProperties::default());
+    }
+    RemoteSignalProperties::try_from(properties).map_err(|err| {
+        wasm_error!(WasmErrorInner::Guest(format!(
+            "Failed to decode the remote signal settings from the DNA properties: {err:?}"
+        )))
+    })
+}
+
+/// Sends each signal as a remote signal to the agents interested in the
+/// nodes it touches: the subscribers of the nodes, the agents themselves,
+/// the other agents linked from the nodes and, if configured, all agents,
+/// in this order of precedence if the fan-out limit is hit. The calling
+/// agent is left out.
+///
+/// This runs in every post_commit and costs one get_links host call for the
+/// ToAgent links of all touched nodes, plus the links of the all agents
+/// anchor if enabled. These are network gets, so each commit waits on
+/// other peers, which is noticeable for agents with a bad connection.
+pub(crate) fn send_remote_signals(signals: Vec<SignalKind>) -> ExternResult<()> {
+    if signals.is_empty() {
+        return Ok(());
+    }
+    let properties = remote_signal_properties()?;
+    let limit = properties
+        .remote_signal_fan_out_limit
+        .unwrap_or(DEFAULT_REMOTE_SIGNAL_FAN_OUT_LIMIT);
+    let me = agent_info()?.agent_initial_pubkey;
+
+    let signal_nodes: Vec<Vec<NodeId>> = signals.iter().map(touched_nodes).collect();
+    let mut nodes: Vec<NodeId> = Vec::new();
+    for node_id in signal_nodes.iter().flatten() {
+        if !nodes.contains(node_id) {
+            nodes.push(node_id.clone());
+        }
+    }
+    let linked_agents: HashMap<NodeId, LinkedAgents> = nodes
+        .iter()
+        .cloned()
+        .zip(get_linked_agents_many(&nodes)?)
+        .collect();
+    let all_agents = match properties.remote_signal_all_agents.unwrap_or(false) {
+        true => get_linked_agents_many(&[NodeId::Anchor(SIMPLE_HOLOCHAIN_ALL_AGENTS.into())])?
+            .pop()
+            .map(|linked| [linked.subscribers, linked.others].concat())
+            .unwrap_or_default(),
+        false => Vec::new(),
+    };
+
+    for (signal, nodes) in signals.into_iter().zip(signal_nodes) {
+        let mut agents: Vec<AgentPubKey> = Vec::new();
+        let linked: Vec<&LinkedAgents> = nodes
+            .iter()
+            .filter_map(|node_id| linked_agents.get(node_id))
+            .collect();
+        let candidates = linked
+            .iter()
+            .flat_map(|linked| linked.subscribers.iter().cloned())
+            .chain(nodes.iter().filter_map(|node_id| match node_id {
+                NodeId::Agent(agent) => Some(agent.clone()),
+                _ => None,
+            }))
+            .chain(
+                linked
+                    .iter()
+                    .flat_map(|linked| linked.others.iter().cloned()),
+            )
+            .chain(all_agents.iter().cloned());
+        for agent in candidates {
+            if agents.len() >= limit {
+                break;
+            }
+            if agent != me && !agents.contains(&agent) {
+                agents.push(agent);
+            }
+        }
+        if agents.is_empty() {
+            continue;
+        }
+        let encoded_signal = ExternIO::encode(Signal::Remote(signal))
+            .map_err(|err| wasm_error!(WasmErrorInner::Guest(err.into())))?;
+        send_remote_signal(encoded_signal, agents)?;
+    }
+    Ok(())
+}
+
+/// The nodes whose content or links a signal is about
+fn touched_nodes(signal: &SignalKind) -> Vec<NodeId> {
+    match signal {
+        SignalKind::ThingCreated { thing } | SignalKind::ThingUpdated { thing, .. } => {
+            vec![NodeId::Thing(thing.id.clone())]
+        }
+        SignalKind::ThingDeleted { id } => vec![NodeId::Thing(id.clone())],
+        SignalKind::LinksCreated { links } => links
+            .iter()
+            .flat_map(|link| [link.src.clone(), link.dst.clone()])
+            .collect(),
+        SignalKind::LinksDeleted { links } => links
+            .iter()
+            .flat_map(|link| [link.src.clone(), link.dst.clone()])
+            .collect(),
+    }
+}
+
+/// The agents linked from a node
+#[derive(Default)]
+struct LinkedAgents {
+    /// Agents linked with the subscriber tag
+    subscribers: Vec<AgentPubKey>,
+    /// Agents linked with any other tag
+    others: Vec<AgentPubKey>,
+}
+
+/// Gets the agents linked from each of the nodes in a single host call
+fn get_linked_agents_many(node_ids: &[NodeId]) -> ExternResult<Vec<LinkedAgents>> {
+    let links = get_links_many(
+        node_ids
+            .iter()
+            .map(|node_id| {
+                Ok(GetLinksInputBuilder::try_new(
+                    linkable_hash_from_node_id(node_id.clone())?,
+                    LinkTypes::ToAgent,
+                )?
+                .build())
+            })
+            .collect::<ExternResult<Vec<GetLinksInput>>>()?,
+    )?;
+    Ok(links
+        .into_iter()
+        .map(|links| {
+            let mut linked_agents = LinkedAgents::default();
+            for link in links {
+                let Some(agent) = link.target.clone().into_agent_pub_key() else {
+                    continue;
+                };
+                match deserialize_link_tag(link.tag.0) {
+                    Ok(c) if is_subscription_link(&c) => linked_agents.subscribers.push(agent),
+                    _ => linked_agents.others.push(agent),
+                }
+            }
+            linked_agents
+        })
+        .collect())
+}
@@ -0,0 +1,416 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::api::{
+    get_links_from_nodes, link_type_for_node_id, node_id_from_link, GetLinkedNodesInput,
+};
+use crate::NodeLinkMeta;
+use generic_zome_integrity::*;
+use hdk::prelude::*;
+
+/// Which way the edges of the traversed graph point
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(tag = "type")]
+pub enum TraversalDirection {
+    /// Follow the links stored at a node to the nodes they point to
+    #[default]
+    Outgoing,
+    /// Follow the links pointing to a node back to the nodes they come
+    /// from, i.e. the returned edges point from the linked nodes to the
+    /// node. Links are only indexed at the node they are stored at, so
+    /// only links that have a backlink or were created in the From
+    /// direction can be followed.
+    Incoming,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct HopFilter {
+    /// If provided, only links whose user tag starts with these bytes are
+    /// followed in this hop
+    pub tag_prefix: Option<Vec<u8>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct TraverseFilters {
+    /// Filter per hop, i.e. `hops[0]` applies to the links of the start
+    /// node, `hops[1]` to the links of its neighbours and so on. Hops
+    /// without a filter follow all links.
+    #[serde(default)]
+    pub hops: Vec<HopFilter>,
+    #[serde(default)]
+    pub direction: TraversalDirection,
+    /// If provided, only links of these types are followed, i.e. only nodes
+    /// of the respective type are visited. Must be ToThing, ToAgent or
+    /// ToAnchor.
+    pub link_types: Option<Vec<LinkTypes>>,
+    /// Maximum number of nodes to visit, including the start node
+    pub max_nodes: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TraverseInput {
+    pub start: NodeId,
+    pub max_depth: usize,
+    #[serde(default)]
+    pub filters: TraverseFilters,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TraversedNode {
+    pub node_id: NodeId,
+    /// Number of hops from the start node
+    pub depth: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Subgraph {
+    /// The visited nodes in breadth-first order, starting with the start node
+    pub nodes: Vec<TraversedNode>,
+    /// The followed links between the visited nodes
+    pub edges: Vec<NodeLinkMeta>,
+    /// Whether the traversal stopped early because max_nodes was reached
+    pub truncated: bool,
+}
+
+/// Walks the links from the start node breadth-first up to max_depth hops
+/// and returns the visited nodes together with the links between them.
+/// Every node is only visited once, so cycles are fine. The links of all
+/// nodes at the same depth are fetched at once.
+#[hdk_extern]
+pub fn traverse(input: TraverseInput) -> ExternResult<Subgraph> {
+    let max_nodes = input.filters.max_nodes.unwrap_or(usize::MAX);
+    let mut visited: HashSet<NodeId> = HashSet::from([input.start.clone()]);
+    let mut level: Vec<NodeId> = vec![input.start];
+    let mut depth = 0;
+    let mut subgraph = Subgraph {
+        nodes: Vec::new(),
+        edges: Vec::new(),
+        truncated: false,
+    };
+
+    while !level.is_empty() {
+        let neighbours = match depth < input.max_depth {
+            true => {
+                let hop_filter = input.filters.hops.get(depth).cloned();
+                get_neighbours(&level, &hop_filter.unwrap_or_default(), &input.filters)?
+            }
+            false => vec![Vec::new(); level.len()],
+        };
+        let mut next_level = Vec::new();
+        for (node_id, node_neighbours) in level.into_iter().zip(neighbours) {
+            for (neighbour, edge) in node_neighbours {
+                if !visited.contains(&neighbour) {
+                    if visited.len() >= max_nodes {
+                        subgraph.truncated = true;
+                        continue;
+                    }
+                    visited.insert(neighbour.clone());
+                    next_level.push(neighbour);
+                }
+                subgraph.edges.push(edge);
+            }
+            subgraph.nodes.push(TraversedNode { node_id, depth });
+        }
+        level = next_level;
+        depth += 1;
+    }
+    Ok(subgraph)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FindPathInput {
+    pub from: NodeId,
+    pub to: NodeId,
+    /// Maximum number of links in the path
+    pub max_depth: usize,
+    /// If provided, only links whose user tag is one of these are followed
+    pub allowed_tags: Option<Vec<Vec<u8>>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GraphPath {
+    /// The nodes of the path, starting with `from` and ending with `to`
+    pub nodes: Vec<NodeId>,
+    /// The links connecting the nodes, i.e. `links[i]` connects `nodes[i]`
+    /// and `nodes[i + 1]`. A link may point in either direction.
+    pub links: Vec<NodeLinkMeta>,
+}
+
+/// For each node reached by one side of the search, the node it was reached
+/// from and the link connecting them
+type SearchParents = HashMap<NodeId, Option<(NodeId, NodeLinkMeta)>>;
+
+/// Finds a shortest path between two nodes by searching breadth-first from
+/// both ends at once, always expanding the side with the smaller frontier.
+/// Once one side has no nodes left to expand, the search goes on from the
+/// other side only. Links are followed regardless of their direction since
+/// the links pointing to a node are only visible through backlinks. Returns
+/// None if there is no path of at most max_depth links.
+#[hdk_extern]
+pub fn find_path(input: FindPathInput) -> ExternResult<Option<GraphPath>> {
+    if input.from == input.to {
+        return Ok(Some(GraphPath {
+            nodes: vec![input.from],
+            links: Vec::new(),
+        }));
+    }
+    let mut forward_parents: SearchParents = HashMap::from([(input.from.clone(), None)]);
+    let mut backward_parents: SearchParents = HashMap::from([(input.to.clone(), None)]);
+    let mut forward_frontier = vec![input.from.clone()];
+    let mut backward_frontier = vec![input.to.clone()];
+    // Number of levels each side has reached. The side that has run out of
+    // nodes keeps its last level, so the paths found later are still at most
+    // forward_depth + backward_depth links long.
+    let mut forward_depth = 0;
+    let mut backward_depth = 0;
+
+    while forward_depth + backward_depth < input.max_depth
+        && !(forward_frontier.is_empty() && backward_frontier.is_empty())
+    {
+        let expand_forward = !forward_frontier.is_empty()
+            && (backward_frontier.is_empty() || forward_frontier.len() <= backward_frontier.len());
+        let (frontier, parents, other_parents, depth) = if expand_forward {
+            (
+                &mut forward_frontier,
+                &mut forward_parents,
+                &backward_parents,
+                &mut forward_depth,
+            )
+        } else {
+            (
+                &mut backward_frontier,
+                &mut backward_parents,
+                &forward_parents,
+                &mut backward_depth,
+            )
+        };
+        let neighbours =
+            get_neighbours(frontier, &HopFilter::default(), &TraverseFilters::default())?;
+        let mut next_frontier = Vec::new();
+        for (node_id, node_neighbours) in frontier.iter().zip(neighbours) {
+            for (neighbour, link) in node_neighbours {
+                if let Some(allowed_tags) = &input.allowed_tags {
+                    match &link.meta_tag.tag {
+                        Some(tag) if allowed_tags.contains(tag) => (),
+                        _ => continue,
+                    }
+                }
+                if parents.contains_key(&neighbour) {
+                    continue;
+                }
+                parents.insert(neighbour.clone(), Some((node_id.clone(), link)));
+                if other_parents.contains_key(&neighbour) {
+                    return Ok(Some(build_path(
+                        neighbour,
+                        &forward_parents,
+                        &backward_parents,
+                    )));
+                }
+                next_frontier.push(neighbour);
+            }
+        }
+        if !next_frontier.is_empty() {
+            *depth += 1;
+        }
+        *frontier = next_frontier;
+    }
+    Ok(None)
+}
+
+/// Joins the paths from `from` to the meeting node and from the meeting
+/// node to `to`
+fn build_path(
+    meeting_node: NodeId,
+    forward_parents: &SearchParents,
+    backward_parents: &SearchParents,
+) -> GraphPath {
+    let mut nodes = vec![meeting_node.clone()];
+    let mut links = Vec::new();
+    let mut current = meeting_node.clone();
+    while let Some(Some((parent, link))) = forward_parents.get(&current) {
+        nodes.push(parent.clone());
+        links.push(link.clone());
+        current = parent.clone();
+    }
+    nodes.reverse();
+    links.reverse();
+    current = meeting_node;
+    while let Some(Some((parent, link))) = backward_parents.get(&current) {
+        nodes.push(parent.clone());
+        links.push(link.clone());
+        current = parent.clone();
+    }
+    GraphPath { nodes, links }
+}
+
+/// Gets the nodes linked from each of the given nodes in the direction of
+/// the filters, each together with the edge connecting them. The links of
+/// all nodes are fetched in a single host call.
+pub(crate) fn get_neighbours(
+    node_ids: &[NodeId],
+    hop_filter: &HopFilter,
+    filters: &TraverseFilters,
+) -> ExternResult<Vec<Vec<(NodeId, NodeLinkMeta)>>> {
+    let link_types = filters.link_types.clone().unwrap_or(vec![
+        LinkTypes::ToThing,
+        LinkTypes::ToAnchor,
+        LinkTypes::ToAgent,
+    ]);
+    if link_types
+        .iter()
+        .any(|link_type| matches!(link_type, LinkTypes::ThingUpdates))
+    {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Only ToThing, ToAgent and ToAnchor links can be traversed.".into()
+        )));
+    }
+    if link_types.is_empty() {
+        return Ok(vec![Vec::new(); node_ids.len()]);
+    }
+    let inputs: Vec<GetLinkedNodesInput> = node_ids
+        .iter()
+        .map(|node_id| GetLinkedNodesInput {
+            tag_prefix: hop_filter.tag_prefix.clone(),
+            ..node_id.clone().into()
+        })
+        .collect();
+    let queries: Vec<(&GetLinkedNodesInput, LinkTypes)> = inputs
+        .iter()
+        .flat_map(|input| link_types.iter().map(move |link_type| (input, *link_type)))
+        .collect();
+    let links: Vec<Vec<(Link, LinkTagContent)>> = get_links_from_nodes(&queries)?
+        .chunks(link_types.len())
+        .map(|chunk| chunk.concat())
+        .collect();
+    match filters.direction {
+        TraversalDirection::Outgoing => Ok(node_ids
+            .iter()
+            .zip(links)
+            .map(|(node_id, links)| {
+                links
+                    .into_iter()
+                    .filter_map(|(link, link_tag_content)| {
+                        let neighbour = node_id_from_link(link.clone(), &link_tag_content)?;
+                        Some((
+                            neighbour.clone(),
+                            NodeLinkMeta {
+                                src: node_id.clone(),
+                                dst: neighbour,
+                                meta_tag: link_tag_content,
+                                create_action_hash: link.create_link_hash,
+                            },
+                        ))
+                    })
+                    .collect()
+            })
+            .collect()),
+        TraversalDirection::Incoming => get_incoming_neighbours(node_ids, links, hop_filter),
+    }
+}
+
+/// Derives the edges pointing to each node from the links stored at it.
+/// Links with a backlink_action_hash point away from the node and are left
+/// out. Links created in the From direction are stored at the node they
+/// point to and name it as their target node, so they describe the
+/// incoming edge themselves. The other links are either
+/// backlinks, whose edge is the link at the other end naming them as its
+/// backlink, or links pointing away from the node, for which no such link
+/// exists.
+fn get_incoming_neighbours(
+    node_ids: &[NodeId],
+    links: Vec<Vec<(Link, LinkTagContent)>>,
+    hop_filter: &HopFilter,
+) -> ExternResult<Vec<Vec<(NodeId, NodeLinkMeta)>>> {
+    let is_from_link = |node_id: &NodeId, link_tag_content: &LinkTagContent| {
+        link_tag_content.target_node_id == *node_id
+    };
+
+    // 1. Get the links stored at the other end of the possible backlinks.
+    // A forward link has the same user tag as its backlink.
+    let mut counterpart_queries: Vec<(NodeId, LinkTypes)> = Vec::new();
+    for (node_id, links) in node_ids.iter().zip(&links) {
+        for (link, link_tag_content) in links {
+            if link_tag_content.backlink_action_hash.is_some()
+                || is_from_link(node_id, link_tag_content)
+            {
+                continue;
+            }
+            let Some(neighbour) = node_id_from_link(link.clone(), link_tag_content) else {
+                continue;
+            };
+            let query = (neighbour, link_type_for_node_id(node_id));
+            if !counterpart_queries.contains(&query) {
+                counterpart_queries.push(query);
+            }
+        }
+    }
+    let counterpart_inputs: Vec<GetLinkedNodesInput> = counterpart_queries
+        .iter()
+        .map(|(neighbour, _)| GetLinkedNodesInput {
+            tag_prefix: hop_filter.tag_prefix.clone(),
+            ..neighbour.clone().into()
+        })
+        .collect();
+    let forward_links: HashMap<ActionHash, (ActionHash, LinkTagContent)> = get_links_from_nodes(
+        &counterpart_inputs
+            .iter()
+            .zip(&counterpart_queries)
+            .map(|(input, (_, link_type))| (input, *link_type))
+            .collect::<Vec<(&GetLinkedNodesInput, LinkTypes)>>(),
+    )?
+    .into_iter()
+    .flatten()
+    .filter_map(|(link, link_tag_content)| {
+        let backlink_action_hash = link_tag_content.backlink_action_hash.clone()?;
+        Some((
+            backlink_action_hash,
+            (link.create_link_hash, link_tag_content),
+        ))
+    })
+    .collect();
+
+    // 2. Turn the From links and the backlinks into incoming edges
+    Ok(node_ids
+        .iter()
+        .zip(links)
+        .map(|(node_id, links)| {
+            links
+                .into_iter()
+                .filter_map(|(link, link_tag_content)| {
+                    if link_tag_content.backlink_action_hash.is_some() {
+                        return None;
+                    }
+                    let (neighbour, meta_tag, create_action_hash) =
+                        if is_from_link(node_id, &link_tag_content) {
+                            let neighbour = from_link_source(&link)?;
+                            (neighbour, link_tag_content, link.create_link_hash)
+                        } else {
+                            let neighbour = node_id_from_link(link.clone(), &link_tag_content)?;
+                            let (create_action_hash, meta_tag) =
+                                forward_links.get(&link.create_link_hash)?.clone();
+                            (neighbour, meta_tag, create_action_hash)
+                        };
+                    Some((
+                        neighbour.clone(),
+                        NodeLinkMeta {
+                            src: neighbour,
+                            dst: node_id.clone(),
+                            meta_tag,
+                            create_action_hash,
+                        },
+                    ))
+                })
+                .collect()
+        })
+        .collect())
+}
+
+/// The node a link created in the From direction comes from, i.e. its
+/// target. Its tag names the node it points to instead, so the anchor
+/// strings of links from anchors are unknown.
+fn from_link_source(link: &Link) -> Option<NodeId> {
+    match LinkTypes::from_type(link.zome_index, link.link_type).ok()?? {
+        LinkTypes::ToThing => link.target.clone().into_action_hash().map(NodeId::Thing),
+        LinkTypes::ToAgent => link.target.clone().into_agent_pub_key().map(NodeId::Agent),
+        _ => None,
+    }
+}
@@ -0,0 +1,100 @@
+use crate::api::{
+    link_from_create_link_action, linkable_hash_from_node_id, node_id_from_link, LinkCursor,
+};
+use crate::batch::get_link_details_many;
+use crate::NodeLinkMeta;
+use generic_zome_integrity::*;
+use hdk::prelude::*;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GetLinkHistoryInput {
+    pub node_id: NodeId,
+    /// If provided, only links of this type are returned. Must be ToThing,
+    /// ToAgent or ToAnchor. Otherwise links of all three types are returned.
+    pub link_type: Option<LinkTypes>,
+    /// Defaults to Network
+    pub get_strategy: Option<GetStrategy>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LinkDeletion {
+    pub delete_action_hash: ActionHash,
+    pub deleted_by: AgentPubKey,
+    pub deleted_at: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LinkHistoryEntry {
+    pub link: NodeLinkMeta,
+    pub created_by: AgentPubKey,
+    pub created_at: Timestamp,
+    /// All DeleteLink actions of this link, oldest first. Empty if the link
+    /// is live.
+    pub deletes: Vec<LinkDeletion>,
+}
+
+/// Gets all links ever created from a node, including the deleted ones
+/// together with who deleted them and when. Ordered by creation time.
+#[hdk_extern]
+pub fn get_link_history(input: GetLinkHistoryInput) -> ExternResult<Vec<LinkHistoryEntry>> {
+    let link_types = match input.link_type {
+        Some(LinkTypes::ThingUpdates) => {
+            return Err(wasm_error!(WasmErrorInner::Guest(
+                "Only the history of ToThing, ToAgent and ToAnchor links can be fetched.".into()
+            )))
+        }
+        Some(link_type) => vec![link_type],
+        None => vec![LinkTypes::ToThing, LinkTypes::ToAnchor, LinkTypes::ToAgent],
+    };
+    let strategy = input.get_strategy.unwrap_or(GetStrategy::Network);
+    let base = linkable_hash_from_node_id(input.node_id.clone())?;
+    let link_details = get_link_details_many(
+        link_types
+            .into_iter()
+            .map(|link_type| {
+                Ok(GetLinksInputBuilder::try_new(base.clone(), link_type)?
+                    .get_options(strategy)
+                    .build())
+            })
+            .collect::<ExternResult<Vec<GetLinksInput>>>()?,
+    )?;
+
+    let mut entries: Vec<(LinkCursor, LinkHistoryEntry)> = Vec::new();
+    for (create, deletes) in link_details.into_iter().flat_map(|d| d.into_inner()) {
+        let Some(link) = link_from_create_link_action(&create) else {
+            continue;
+        };
+        let Ok(meta_tag) = deserialize_link_tag(link.tag.0.clone()) else {
+            continue;
+        };
+        let cursor = LinkCursor::from(&link);
+        let Some(dst) = node_id_from_link(link.clone(), &meta_tag) else {
+            continue;
+        };
+        let mut deletes: Vec<LinkDeletion> = deletes
+            .into_iter()
+            .map(|delete| LinkDeletion {
+                delete_action_hash: delete.action_address().clone(),
+                deleted_by: delete.action().author().clone(),
+                deleted_at: delete.action().timestamp(),
+            })
+            .collect();
+        deletes.sort_by_key(|delete| delete.deleted_at);
+        entries.push((
+            cursor,
+            LinkHistoryEntry {
+                link: NodeLinkMeta {
+                    src: input.node_id.clone(),
+                    dst,
+                    meta_tag,
+                    create_action_hash: link.create_link_hash,
+                },
+                created_by: link.author,
+                created_at: link.timestamp,
+                deletes,
+            },
+        ));
+    }
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Ok(entries.into_iter().map(|(_, entry)| entry).collect())
+}
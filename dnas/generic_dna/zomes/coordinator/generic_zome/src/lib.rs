@@ -1,4 +1,19 @@
 pub mod api;
+pub mod batch;
+pub mod chain;
+pub mod expiry;
+pub mod fan_out;
+pub mod graph;
+pub mod history;
+pub mod ordered;
+pub mod orphans;
+pub mod query;
+pub mod repair;
+pub mod signals;
+pub mod sort;
+pub mod subscriptions;
+pub mod sync;
+pub mod trash;
 use generic_zome_integrity::*;
 use hdk::prelude::*;
 
@@ -17,6 +32,11 @@ pub fn init() -> ExternResult<InitCallbackResult> {
 
     // register own public key on global anchor
     add_agent_to_anchor(())?;
+
+    #[cfg(feature = "scheduled-cleanup")]
+    schedule("scheduled_delete_expired")?;
+    #[cfg(feature = "scheduled-repair")]
+    schedule("scheduled_repair_graph")?;
     Ok(InitCallbackResult::Pass)
 }
 
@@ -43,6 +63,19 @@ pub struct Thing {
     pub creator: AgentPubKey,
     pub created_at: Timestamp,
     pub updated_at: Option<Timestamp>,
+    /// Set if the Thing is known to be deleted. Only filled in by reads
+    /// that look at the details of a Thing, like get_thing_details.
+    #[serde(default)]
+    pub deleted: Option<ThingDeletion>,
+    /// Time from which on the Thing is treated as absent, if any
+    #[serde(default)]
+    pub expires_at: Option<Timestamp>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ThingDeletion {
+    pub by: AgentPubKey,
+    pub at: Timestamp,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -53,7 +86,7 @@ pub enum Signal {
 }
 
 /// Don't modify this enum if you want the scaffolding tool to generate appropriate signals for your entries and links
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(tag = "type")]
 pub enum SignalKind {
     ThingCreated {
@@ -119,22 +152,46 @@ pub fn add_agent_to_anchor(_: ()) -> ExternResult<ActionHash> {
         path.path_entry_hash()?,
         my_agent_pubkey.clone(),
         LinkTypes::ToAgent,
-        derive_link_tag(None, None, NodeId::Agent(my_agent_pubkey), None, None)?.0,
+        derive_link_tag(
+            None,
+            None,
+            NodeId::Agent(my_agent_pubkey),
+            None,
+            None,
+            None,
+            None,
+            Some(SIMPLE_HOLOCHAIN_ALL_AGENTS.into()),
+        )?
+        .0,
     )
 }
 
+/// Returns the maximum size in bytes of the user tag of a link pointing to the
+/// given node. Links with larger tags fail with a `TagTooLarge` error.
+#[hdk_extern]
+pub fn max_user_tag_size(target_node_id: NodeId) -> ExternResult<usize> {
+    generic_zome_integrity::max_user_tag_size(target_node_id)
+}
+
+/// Derives the link tag for a link pointing to target_node_id. source_anchor
+/// has to be set if the base of the link is an anchor. Fails with a
+/// `TagTooLarge { max, actual }` error if the user tag does not fit into the
+/// space that is left next to the metadata.
 pub fn derive_link_tag(
     input: Option<Vec<u8>>,
     backlink_action_hash: Option<ActionHash>,
     target_node_id: NodeId,
     thing_created_at: Option<Timestamp>,
     thing_created_by: Option<AgentPubKey>,
+    position: Option<String>,
+    expires_at: Option<Timestamp>,
+    source_anchor: Option<String>,
 ) -> ExternResult<(LinkTag, LinkTagContent)> {
     if let NodeId::Thing(_) = target_node_id {
-        if let None = thing_created_at {
+        if thing_created_at.is_none() {
             return Err(wasm_error!(WasmErrorInner::Guest("To derive a link tag of type 'Thing', the thing_created_at field must be provided.".into())));
         }
-        if let None = thing_created_by {
+        if thing_created_by.is_none() {
             return Err(wasm_error!(WasmErrorInner::Guest("To derive a link tag of type 'Thing', the thing_created_by field must be provided.".into())));
         }
     }
@@ -144,106 +201,30 @@ pub fn derive_link_tag(
         target_node_id,
         thing_created_at,
         thing_created_by,
+        position,
+        expires_at,
+        source_anchor,
     };
     let serialized_content = serialize_link_tag(link_tag_content.clone())?;
     Ok((LinkTag::from(serialized_content), link_tag_content))
 }
 
-// /// Whenever an action is committed, we emit a signal to the UI elements to reactively update them
-// #[hdk_extern(infallible)]
-// pub fn post_commit(committed_actions: Vec<SignedActionHashed>) {
-//     /// Don't modify this loop if you want the scaffolding tool to generate appropriate signals for your entries and links
-//     for action in committed_actions {
-//         if let Err(err) = signal_action(action) {
-//             error!("Error signaling new action: {:?}", err);
-//         }
-//     }
-// }
-
-// /// Don't modify this function if you want the scaffolding tool to generate appropriate signals for your entries and links
-// fn signal_action(action: SignedActionHashed) -> ExternResult<()> {
-//     match action.hashed.content.clone() {
-//         Action::CreateLink(create_link) => {
-//             if let Ok(Some(link_type)) =
-//                 LinkTypes::from_type(create_link.zome_index, create_link.link_type)
-//             {
-//                 emit_signal(Signal::LinkCreated { action, link_type })?;
-//             }
-//             Ok(())
-//         }
-//         Action::DeleteLink(delete_link) => {
-//             let record = get(delete_link.link_add_address.clone(), GetOptions::default())?.ok_or(
-//                 wasm_error!(WasmErrorInner::Guest(
-//                     "Failed to fetch CreateLink action".to_string()
-//                 )),
-//             )?;
-//             match record.action() {
-//                 Action::CreateLink(create_link) => {
-//                     if let Ok(Some(link_type)) =
-//                         LinkTypes::from_type(create_link.zome_index, create_link.link_type)
-//                     {
-//                         emit_signal(Signal::LinkDeleted {
-//                             action,
-//                             link_type,
-//                             create_link_action: record.signed_action.clone(),
-//                         })?;
-//                     }
-//                     Ok(())
-//                 }
-//                 _ => Err(wasm_error!(WasmErrorInner::Guest(
-//                     "Create Link should exist".to_string()
-//                 ))),
-//             }
-//         }
-//         Action::Create(_create) => {
-//             if let Ok(Some(app_entry)) = get_entry_for_action(&action.hashed.hash) {
-//                 emit_signal(Signal::EntryCreated { action, app_entry })?;
-//             }
-//             Ok(())
-//         }
-//         Action::Update(update) => {
-//             if let Ok(Some(app_entry)) = get_entry_for_action(&action.hashed.hash) {
-//                 if let Ok(Some(original_app_entry)) =
-//                     get_entry_for_action(&update.original_action_address)
-//                 {
-//                     emit_signal(Signal::EntryUpdated {
-//                         action,
-//                         app_entry,
-//                         original_app_entry,
-//                     })?;
-//                 }
-//             }
-//             Ok(())
-//         }
-//         Action::Delete(delete) => {
-//             if let Ok(Some(original_app_entry)) = get_entry_for_action(&delete.deletes_address) {
-//                 emit_signal(Signal::EntryDeleted {
-//                     action,
-//                     original_app_entry,
-//                 })?;
-//             }
-//             Ok(())
-//         }
-//         _ => Ok(()),
-//     }
-// }
-
-// fn get_entry_for_action(action_hash: &ActionHash) -> ExternResult<Option<EntryTypes>> {
-//     let record = match get_details(action_hash.clone(), GetOptions::default())? {
-//         Some(Details::Record(record_details)) => record_details.record,
-//         _ => return Ok(None),
-//     };
-//     let entry = match record.entry().as_option() {
-//         Some(entry) => entry,
-//         None => return Ok(None),
-//     };
-//     let (zome_index, entry_index) = match record.action().entry_type() {
-//         Some(EntryType::App(AppEntryDef {
-//             zome_index,
-//             entry_index,
-//             ..
-//         })) => (zome_index, entry_index),
-//         _ => return Ok(None),
-//     };
-//     EntryTypes::deserialize_from_type(*zome_index, *entry_index, entry)
-// }
+/// Whenever actions are committed, we emit signals to the UI elements to
+/// reactively update them and send them on to the agents interested in the
+/// touched nodes. This covers all ways of committing, including `init` and
+/// calls from other zomes. Finding the interested agents takes a get_links
+/// over the network on every commit, see `fan_out::send_remote_signals`.
+#[hdk_extern(infallible)]
+pub fn post_commit(committed_actions: Vec<SignedActionHashed>) {
+    if let Err(err) = signal_actions(&committed_actions) {
+        error!("Error signaling committed actions: {:?}", err);
+    }
+}
+
+fn signal_actions(committed_actions: &[SignedActionHashed]) -> ExternResult<()> {
+    let signal_kinds = signals::signals_for_actions(committed_actions)?;
+    for signal_kind in signal_kinds.iter().cloned() {
+        emit_signal(Signal::Local(signal_kind))?;
+    }
+    fan_out::send_remote_signals(signal_kinds)
+}
@@ -0,0 +1,352 @@
+use crate::api::{
+    create_link_from_node_by_id, get_all_links_from_node, node_id_from_link, GetLinkedNodesInput,
+    LinkCursor, LinkDirection, LinkInput,
+};
+use crate::NodeLinkMeta;
+use generic_zome_integrity::*;
+use hdk::prelude::*;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct InsertItemInput {
+    pub collection: NodeId,
+    pub item: NodeId,
+    /// User tag of the links that make up the collection. A node can have
+    /// multiple ordered collections with different tags.
+    pub tag: Option<Vec<u8>>,
+    /// Must be either To or Bidirectional
+    pub direction: LinkDirection,
+    /// The item to insert next to. If None, the item is inserted at the end
+    /// (`insert_after`) or at the start (`insert_before`) of the collection.
+    pub reference: Option<NodeId>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MoveItemInput {
+    pub collection: NodeId,
+    pub item: NodeId,
+    pub tag: Option<Vec<u8>>,
+    /// The item to move the item after. If None, the item is moved to the
+    /// start of the collection.
+    pub after: Option<NodeId>,
+}
+
+/// An item of an ordered collection
+struct CollectionEntry {
+    node_id: NodeId,
+    position: String,
+    link: Link,
+    link_tag_content: LinkTagContent,
+}
+
+/// Inserts an item into an ordered collection right after the reference item.
+/// Fails if the item is already part of the collection, use `move_item` to
+/// change its position instead.
+#[hdk_extern]
+pub fn insert_after(input: InsertItemInput) -> ExternResult<NodeLinkMeta> {
+    let entries = get_collection_entries(&input.collection, &input.tag)?;
+    ensure_not_in_collection(&entries, &input.item)?;
+    let position = match &input.reference {
+        Some(reference) => {
+            let index = find_entry(&entries, reference)?;
+            let position = &entries[index].position;
+            // Entries with the same position (from concurrent inserts) are
+            // skipped as there is no key between them
+            let next = entries[index..]
+                .iter()
+                .find(|entry| &entry.position > position);
+            key_between(Some(position), next.map(|entry| entry.position.as_str()))?
+        }
+        None => key_between(entries.last().map(|entry| entry.position.as_str()), None)?,
+    };
+    insert_item(input, position)
+}
+
+/// Inserts an item into an ordered collection right before the reference
+/// item. Fails if the item is already part of the collection.
+#[hdk_extern]
+pub fn insert_before(input: InsertItemInput) -> ExternResult<NodeLinkMeta> {
+    let entries = get_collection_entries(&input.collection, &input.tag)?;
+    ensure_not_in_collection(&entries, &input.item)?;
+    let position = match &input.reference {
+        Some(reference) => {
+            let index = find_entry(&entries, reference)?;
+            let position = &entries[index].position;
+            let previous = entries[..index]
+                .iter()
+                .rev()
+                .find(|entry| &entry.position < position);
+            key_between(
+                previous.map(|entry| entry.position.as_str()),
+                Some(position),
+            )?
+        }
+        None => key_between(None, entries.first().map(|entry| entry.position.as_str()))?,
+    };
+    insert_item(input, position)
+}
+
+/// Moves an item of an ordered collection by deleting its link and creating
+/// a new one with a position after the given item. The links of all other
+/// items stay untouched.
+#[hdk_extern]
+pub fn move_item(input: MoveItemInput) -> ExternResult<NodeLinkMeta> {
+    let (item_entries, entries): (Vec<CollectionEntry>, Vec<CollectionEntry>) =
+        get_collection_entries(&input.collection, &input.tag)?
+            .into_iter()
+            .partition(|entry| entry.node_id == input.item);
+    if item_entries.is_empty() {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Item to move is not part of the collection.".into()
+        )));
+    }
+    let position = match &input.after {
+        Some(after) => {
+            let index = find_entry(&entries, after)?;
+            let position = &entries[index].position;
+            let next = entries[index..]
+                .iter()
+                .find(|entry| &entry.position > position);
+            key_between(Some(position), next.map(|entry| entry.position.as_str()))?
+        }
+        None => key_between(None, entries.first().map(|entry| entry.position.as_str()))?,
+    };
+
+    let mut direction = LinkDirection::To;
+    for entry in item_entries {
+        if let Some(backlink_action_hash) = entry.link_tag_content.backlink_action_hash {
+            delete_link(backlink_action_hash)?;
+            direction = LinkDirection::Bidirectional;
+        }
+        delete_link(entry.link.create_link_hash)?;
+    }
+
+    insert_item(
+        InsertItemInput {
+            collection: input.collection,
+            item: input.item,
+            tag: input.tag,
+            direction,
+            reference: None,
+        },
+        position,
+    )
+}
+
+fn insert_item(input: InsertItemInput, position: String) -> ExternResult<NodeLinkMeta> {
+    if let LinkDirection::From = input.direction {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Items of ordered collections must be linked in direction To or Bidirectional.".into()
+        )));
+    }
+    let (node_link, _) = create_link_from_node_by_id(
+        input.collection,
+        LinkInput {
+            direction: input.direction,
+            node_id: input.item,
+            tag: input.tag,
+            dedupe: false,
+            position: Some(position),
+            expires_at: None,
+        },
+    )?;
+    Ok(node_link)
+}
+
+/// Gets the items of an ordered collection, i.e. the linked nodes whose link
+/// has exactly the given tag and a position, ordered by position. Items with
+/// the same position are ordered by link timestamp and create link hash.
+fn get_collection_entries(
+    collection: &NodeId,
+    tag: &Option<Vec<u8>>,
+) -> ExternResult<Vec<CollectionEntry>> {
+    let links = get_all_links_from_node(&GetLinkedNodesInput {
+        tag_prefix: tag.clone(),
+        ..collection.clone().into()
+    })?;
+    let mut entries: Vec<CollectionEntry> = links
+        .into_iter()
+        .filter(|(_, link_tag_content)| &link_tag_content.tag == tag)
+        .filter_map(|(link, link_tag_content)| {
+            Some(CollectionEntry {
+                node_id: node_id_from_link(link.clone(), &link_tag_content)?,
+                position: link_tag_content.position.clone()?,
+                link,
+                link_tag_content,
+            })
+        })
+        .collect();
+    entries.sort_by(|a, b| {
+        a.position
+            .cmp(&b.position)
+            .then_with(|| LinkCursor::from(&a.link).cmp(&LinkCursor::from(&b.link)))
+    });
+    Ok(entries)
+}
+
+fn ensure_not_in_collection(entries: &[CollectionEntry], item: &NodeId) -> ExternResult<()> {
+    if entries.iter().any(|entry| &entry.node_id == item) {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "Item is already part of the collection. Use move_item to change its position.".into()
+        )));
+    }
+    Ok(())
+}
+
+fn find_entry(entries: &[CollectionEntry], node_id: &NodeId) -> ExternResult<usize> {
+    entries
+        .iter()
+        .position(|entry| &entry.node_id == node_id)
+        .ok_or(wasm_error!(WasmErrorInner::Guest(
+            "Reference item is not part of the collection.".into()
+        )))
+}
+
+/// Generates a fractional index key that sorts between a and b. A missing a
+/// stands for the start and a missing b for the end of the collection.
+pub fn key_between(a: Option<&str>, b: Option<&str>) -> ExternResult<String> {
+    let to_digits = |key: &str| -> ExternResult<Vec<usize>> {
+        if !is_valid_position(key) {
+            return Err(wasm_error!(WasmErrorInner::Guest(format!(
+                "Invalid position: {key}"
+            ))));
+        }
+        Ok(key
+            .bytes()
+            .filter_map(|b| POSITION_DIGITS.iter().position(|d| *d == b))
+            .collect())
+    };
+    let a = a.map(to_digits).transpose()?.unwrap_or_default();
+    let b = b.map(to_digits).transpose()?;
+    if let Some(b) = &b {
+        if a >= *b {
+            return Err(wasm_error!(WasmErrorInner::Guest(
+                "The first position must sort before the second one.".into()
+            )));
+        }
+    }
+    let key: String = midpoint(&a, b.as_deref())
+        .into_iter()
+        .map(|digit| POSITION_DIGITS[digit] as char)
+        .collect();
+    if key.len() > MAX_POSITION_LEN {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "No more room between the given positions.".into()
+        )));
+    }
+    Ok(key)
+}
+
+/// Digits of a key strictly between a and b, where a < b and b = None means
+/// one past the largest key. Neither a nor b end with the digit 0, so
+/// neither does the result.
+fn midpoint(a: &[usize], b: Option<&[usize]>) -> Vec<usize> {
+    if let Some(b) = b {
+        let common_prefix_len = b
+            .iter()
+            .enumerate()
+            .take_while(|(i, digit)| a.get(*i).copied().unwrap_or(0) == **digit)
+            .count();
+        if common_prefix_len > 0 {
+            let mut key = b[..common_prefix_len].to_vec();
+            let rest_a = a.get(common_prefix_len..).unwrap_or_default();
+            key.extend(midpoint(rest_a, Some(&b[common_prefix_len..])));
+            return key;
+        }
+    }
+    let digit_a = a.first().copied().unwrap_or(0);
+    let digit_b = b.map(|b| b[0]).unwrap_or(POSITION_DIGITS.len());
+    if digit_b - digit_a > 1 {
+        return vec![(digit_a + digit_b) / 2];
+    }
+    match b {
+        // The first digit of b alone sorts before b, since b continues
+        Some(b) if b.len() > 1 => vec![b[0]],
+        _ => {
+            let mut key = vec![digit_a];
+            key.extend(midpoint(a.get(1..).unwrap_or_default(), None));
+            key
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn between(a: Option<&str>, b: Option<&str>) -> String {
+        let key = key_between(a, b).unwrap();
+        assert!(is_valid_position(&key), "invalid key {key}");
+        if let Some(a) = a {
+            assert!(a < key.as_str(), "{key} doesn't sort after {a}");
+        }
+        if let Some(b) = b {
+            assert!(key.as_str() < b, "{key} doesn't sort before {b}");
+        }
+        key
+    }
+
+    #[test]
+    fn generates_keys_without_bounds() {
+        let first = between(None, None);
+        assert_eq!(first, "V");
+        between(None, Some(&first));
+        between(Some(&first), None);
+        between(None, Some("1"));
+        between(Some("z"), None);
+    }
+
+    #[test]
+    fn generates_keys_between_adjacent_digits() {
+        assert_eq!(between(Some("1"), Some("2")), "1V");
+        assert_eq!(between(Some("y"), Some("z")), "yV");
+        between(Some("1z"), Some("2"));
+        between(Some("1"), Some("11"));
+    }
+
+    #[test]
+    fn generates_keys_between_a_key_and_its_extension() {
+        assert_eq!(between(Some("a"), Some("a5")), "a2");
+        between(Some("a"), Some("a1"));
+        between(Some("a"), Some("a01"));
+        between(Some("a5"), Some("a51"));
+    }
+
+    #[test]
+    fn keeps_generating_keys_between_the_same_bounds() {
+        let mut b = "2".to_string();
+        for _ in 0..100 {
+            b = between(Some("1"), Some(&b));
+        }
+        let mut a = "1".to_string();
+        for _ in 0..100 {
+            a = between(Some(&a), Some("2"));
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_positions() {
+        for invalid in ["", "a0", "a-", "ä"] {
+            assert!(key_between(Some(invalid), None).is_err(), "{invalid:?}");
+            assert!(key_between(None, Some(invalid)).is_err(), "{invalid:?}");
+        }
+        assert!(key_between(Some(&"1".repeat(MAX_POSITION_LEN + 1)), None).is_err());
+    }
+
+    #[test]
+    fn rejects_bounds_out_of_order() {
+        assert!(key_between(Some("a"), Some("a")).is_err());
+        assert!(key_between(Some("b"), Some("a")).is_err());
+        assert!(key_between(Some("a5"), Some("a")).is_err());
+    }
+
+    #[test]
+    fn rejects_keys_longer_than_the_maximum() {
+        let longest = "z".repeat(MAX_POSITION_LEN);
+        assert!(key_between(Some(&longest), None).is_err());
+
+        let a = "1".repeat(MAX_POSITION_LEN);
+        let b = format!("{}2", "1".repeat(MAX_POSITION_LEN - 1));
+        assert!(key_between(Some(&a), Some(&b)).is_err());
+        between(Some(&a[1..]), Some(&b[1..]));
+    }
+}
@@ -0,0 +1,237 @@
+use crate::api::{
+    create_links_from_node, delete_thing, get_links_from_nodes, link_type_for_node_id,
+    linkable_hash_from_node_id, CreateOrDeleteLinksInput, DeleteThingInput, GetLinkedNodesInput,
+    LinkDirection, LinkInput,
+};
+use crate::batch::{get_latest_things_batched, get_link_details_many};
+use crate::chain::{query_deleted_link_action_hashes, query_my_live_things};
+use crate::expiry::is_expired;
+use crate::Thing;
+use generic_zome_integrity::*;
+use hdk::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type")]
+pub enum OrphanAction {
+    /// Links every orphan from the given anchor
+    Relink { anchor: String },
+    /// Deletes the orphans with the given ids through `delete_thing` with
+    /// delete_backlinks and delete_links_from_creator set, i.e. together
+    /// with all links connected to them that the calling agent can find.
+    /// One-directional links from other agents can't be seen, so the ids
+    /// should come from a previous run that only reported the orphans and
+    /// have been confirmed by the user. Ids of Things that aren't orphans
+    /// (anymore) are skipped.
+    Delete { thing_ids: Vec<ActionHash> },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FindMyOrphansInput {
+    /// What to do with the orphans. If None they are only returned.
+    pub action: Option<OrphanAction>,
+    /// Defaults to Network
+    pub get_strategy: Option<GetStrategy>,
+}
+
+/// A link that may point to one of the Things, identified by where it is
+/// stored
+struct InboundLink {
+    thing_id: ActionHash,
+    base: AnyLinkableHash,
+    link_type: LinkTypes,
+    /// Set if the link has been created by the calling agent
+    create_link_hash: Option<ActionHash>,
+    /// Set if the link is only known through its backlink at the Thing
+    backlink_action_hash: Option<ActionHash>,
+}
+
+/// Finds the Things created by the calling agent that no live link points
+/// to and which can therefore not be reached by anyone else. Inbound links
+/// are looked up in the source chain of the calling agent and through the
+/// backlinks of bidirectional links. One-directional links created by other
+/// agents are not stored at the Thing, so Things only reachable through such
+/// links are reported as orphans as well.
+#[hdk_extern]
+pub fn find_my_orphans(input: FindMyOrphansInput) -> ExternResult<Vec<Thing>> {
+    let strategy = input.get_strategy.unwrap_or(GetStrategy::Network);
+    let now = sys_time()?;
+    let thing_ids: Vec<ActionHash> = query_my_live_things()?
+        .into_iter()
+        .filter(
+            |record| match record.entry().to_app_option::<ThingEntry>() {
+                Ok(Some(thing_entry)) => !is_expired(thing_entry.expires_at, now),
+                _ => false,
+            },
+        )
+        .map(|record| record.action_address().clone())
+        .collect();
+    let thing_id_set: HashSet<ActionHash> = thing_ids.iter().cloned().collect();
+
+    // 1. Links to the Things that the calling agent created themselves
+    let deleted_links = query_deleted_link_action_hashes()?;
+    let mut inbound_links: Vec<InboundLink> = Vec::new();
+    for record in query(ChainQueryFilter::new().action_type(ActionType::CreateLink))? {
+        let Action::CreateLink(create_link) = record.action() else {
+            continue;
+        };
+        let Some(thing_id) = create_link.target_address.clone().into_action_hash() else {
+            continue;
+        };
+        if !thing_id_set.contains(&thing_id)
+            || create_link.base_address == thing_id.clone().into()
+            || deleted_links.contains(record.action_address())
+        {
+            continue;
+        }
+        let Ok(Some(link_type @ (LinkTypes::ToThing | LinkTypes::ToAgent | LinkTypes::ToAnchor))) =
+            LinkTypes::from_type(create_link.zome_index, create_link.link_type)
+        else {
+            continue;
+        };
+        inbound_links.push(InboundLink {
+            thing_id,
+            base: create_link.base_address.clone(),
+            link_type,
+            create_link_hash: Some(record.action_address().clone()),
+            backlink_action_hash: None,
+        });
+    }
+
+    // 2. Links to the Things that have a backlink stored at the Thing
+    let inputs: Vec<GetLinkedNodesInput> = thing_ids
+        .iter()
+        .map(|thing_id| GetLinkedNodesInput {
+            get_strategy: Some(strategy),
+            ..NodeId::Thing(thing_id.clone()).into()
+        })
+        .collect();
+    let queries: Vec<(&GetLinkedNodesInput, LinkTypes)> = inputs
+        .iter()
+        .flat_map(|input| {
+            [LinkTypes::ToThing, LinkTypes::ToAnchor, LinkTypes::ToAgent].map(|t| (input, t))
+        })
+        .collect();
+    for ((input, _), links) in queries.iter().zip(get_links_from_nodes(&queries)?) {
+        let NodeId::Thing(thing_id) = &input.node_id else {
+            continue;
+        };
+        for (link, link_tag_content) in links {
+            if link_tag_content.backlink_action_hash.is_some() {
+                continue;
+            }
+            inbound_links.push(InboundLink {
+                thing_id: thing_id.clone(),
+                base: linkable_hash_from_node_id(link_tag_content.target_node_id)?,
+                link_type: link_type_for_node_id(&input.node_id),
+                create_link_hash: None,
+                backlink_action_hash: Some(link.create_link_hash),
+            });
+        }
+    }
+
+    // 3. Check which of the inbound links are live, fetching the links of
+    // each base and link type only once
+    let mut queries: Vec<(AnyLinkableHash, LinkTypes)> = Vec::new();
+    let mut query_indices_by_base: HashMap<AnyLinkableHash, Vec<usize>> = HashMap::new();
+    let mut query_indices: Vec<usize> = Vec::new();
+    for inbound_link in &inbound_links {
+        let indices = query_indices_by_base
+            .entry(inbound_link.base.clone())
+            .or_default();
+        let index = match indices
+            .iter()
+            .find(|i| queries[**i].1 == inbound_link.link_type)
+        {
+            Some(index) => *index,
+            None => {
+                queries.push((inbound_link.base.clone(), inbound_link.link_type));
+                indices.push(queries.len() - 1);
+                queries.len() - 1
+            }
+        };
+        query_indices.push(index);
+    }
+    let link_details: Vec<Vec<(SignedActionHashed, Vec<SignedActionHashed>)>> =
+        get_link_details_many(
+            queries
+                .into_iter()
+                .map(|(base, link_type)| {
+                    Ok(GetLinksInputBuilder::try_new(base, link_type)?
+                        .get_options(strategy)
+                        .build())
+                })
+                .collect::<ExternResult<Vec<GetLinksInput>>>()?,
+        )?
+        .into_iter()
+        .map(|details| details.into_inner())
+        .collect();
+    let mut reachable: HashSet<ActionHash> = HashSet::new();
+    for (inbound_link, index) in inbound_links.into_iter().zip(query_indices) {
+        let is_live = link_details[index].iter().any(|(create, deletes)| {
+            if !deletes.is_empty() {
+                return false;
+            }
+            match (
+                &inbound_link.create_link_hash,
+                &inbound_link.backlink_action_hash,
+            ) {
+                (Some(create_link_hash), _) => create.action_address() == create_link_hash,
+                (None, Some(backlink_action_hash)) => match create.action() {
+                    Action::CreateLink(create_link) => {
+                        deserialize_link_tag(create_link.tag.0.clone())
+                            .map(|c| c.backlink_action_hash.as_ref() == Some(backlink_action_hash))
+                            .unwrap_or(false)
+                    }
+                    _ => false,
+                },
+                (None, None) => false,
+            }
+        });
+        if is_live {
+            reachable.insert(inbound_link.thing_id);
+        }
+    }
+
+    let orphan_ids: Vec<ActionHash> = thing_ids
+        .into_iter()
+        .filter(|thing_id| !reachable.contains(thing_id))
+        .collect();
+    let orphans: Vec<Thing> = get_latest_things_batched(orphan_ids, strategy)?
+        .into_iter()
+        .flatten()
+        .collect();
+
+    // 4. Apply the requested action
+    match input.action {
+        Some(OrphanAction::Relink { anchor }) if !orphans.is_empty() => {
+            create_links_from_node(CreateOrDeleteLinksInput {
+                src: NodeId::Anchor(anchor),
+                links: orphans
+                    .iter()
+                    .map(|thing| LinkInput {
+                        direction: LinkDirection::To,
+                        node_id: NodeId::Thing(thing.id.clone()),
+                        tag: None,
+                        dedupe: true,
+                        position: None,
+                        expires_at: None,
+                    })
+                    .collect(),
+            })?;
+        }
+        Some(OrphanAction::Delete { thing_ids }) => {
+            for thing in orphans.iter().filter(|thing| thing_ids.contains(&thing.id)) {
+                delete_thing(DeleteThingInput {
+                    thing_id: thing.id.clone(),
+                    delete_backlinks: true,
+                    delete_links_from_creator: true,
+                    delete_links: None,
+                })?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(orphans)
+}
@@ -0,0 +1,532 @@
+//! A small pattern language to query the graph, e.g.
+//!
+//! `(anchor "projects")-[tag "task"]->(t:thing)-[tag "assignee"]->(agent me)`
+//!
+//! A pattern is a chain of nodes connected by edges:
+//! - Nodes are written as `([variable:]kind [id])` where kind is one of
+//!   `anchor`, `agent`, `thing` or `node` (any kind). The id is a quoted
+//!   anchor string, base64 encoded agent public key or Thing id, or `me`
+//!   for the calling agent. Nodes with the same variable must be bound to
+//!   the same node.
+//! - Edges are written as `-[filter]->` or `<-[filter]-` where the optional
+//!   filter is `tag "..."` (exact user tag) or `prefix "..."` (user tag
+//!   prefix). Incoming edges (`<-`) are found through the backlinks
+//!   stored at the node on their left and then checked at the node on
+//!   their right.
+//!
+//! The first node needs an id. The pattern is matched from left to right.
+
+use crate::api::{
+    get_links_from_nodes, link_type_for_node_id, node_id_from_link, GetLinkedNodesInput,
+};
+use generic_zome_integrity::*;
+use hdk::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+/// Maximum number of (partial) matches before a query is aborted
+pub const MAX_QUERY_ROWS: usize = 1000;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct QueryGraphResult {
+    /// The variable names of the nodes of the pattern in order. Nodes
+    /// without a variable are named by their index, e.g. `_0`.
+    pub variables: Vec<String>,
+    /// One row per match with the bound node of every variable in the
+    /// order of `variables`
+    pub rows: Vec<Vec<NodeId>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum NodeKind {
+    Anchor,
+    Agent,
+    Thing,
+    Any,
+}
+
+#[derive(Clone, Debug)]
+enum NodeIdPattern {
+    Literal(String),
+    Me,
+}
+
+#[derive(Clone, Debug)]
+struct NodePattern {
+    variable: Option<String>,
+    kind: NodeKind,
+    id: Option<NodeIdPattern>,
+}
+
+#[derive(Clone, Debug)]
+enum TagFilter {
+    Exact(Vec<u8>),
+    Prefix(Vec<u8>),
+}
+
+#[derive(Clone, Debug)]
+struct EdgePattern {
+    incoming: bool,
+    tag_filter: Option<TagFilter>,
+}
+
+#[derive(Clone, Debug)]
+struct GraphPattern {
+    start: NodePattern,
+    steps: Vec<(EdgePattern, NodePattern)>,
+}
+
+/// Parses a pattern and returns all matches in the graph
+#[hdk_extern]
+pub fn query_graph(query: String) -> ExternResult<QueryGraphResult> {
+    let pattern = QueryParser::new(&query).parse()?;
+    let node_patterns: Vec<&NodePattern> = std::iter::once(&pattern.start)
+        .chain(pattern.steps.iter().map(|(_, node)| node))
+        .collect();
+    let variables: Vec<String> = node_patterns
+        .iter()
+        .enumerate()
+        .map(|(i, node)| node.variable.clone().unwrap_or(format!("_{i}")))
+        .collect();
+
+    let start = match resolve_node_id(&pattern.start)? {
+        Some(start) => start,
+        None => {
+            return Err(wasm_error!(WasmErrorInner::Guest(
+                "The first node of a query needs an id.".into()
+            )))
+        }
+    };
+    let mut rows: Vec<Vec<NodeId>> = vec![vec![start]];
+    for (i, (edge, node)) in pattern.steps.iter().enumerate() {
+        let resolved_id = resolve_node_id(node)?;
+        let bound_index = bound_index(&node_patterns, i + 1);
+        // The links of all nodes reached by the previous hop are fetched at once
+        let mut source_indices: HashMap<&NodeId, usize> = HashMap::new();
+        let mut sources: Vec<NodeId> = Vec::new();
+        for row in &rows {
+            source_indices.entry(&row[i]).or_insert_with(|| {
+                sources.push(row[i].clone());
+                sources.len() - 1
+            });
+        }
+        let neighbours = get_matching_neighbours(&sources, edge, &node.kind)?;
+        let mut next_rows = Vec::new();
+        for row in &rows {
+            for neighbour in &neighbours[source_indices[&row[i]]] {
+                if resolved_id.as_ref().is_some_and(|id| id != neighbour) {
+                    continue;
+                }
+                if bound_index.is_some_and(|index| &row[index] != neighbour) {
+                    continue;
+                }
+                let mut next_row = row.clone();
+                next_row.push(neighbour.clone());
+                next_rows.push(next_row);
+                if next_rows.len() > MAX_QUERY_ROWS {
+                    return Err(wasm_error!(WasmErrorInner::Guest(format!(
+                        "Query matches more than {MAX_QUERY_ROWS} rows."
+                    ))));
+                }
+            }
+        }
+        rows = next_rows;
+    }
+    Ok(QueryGraphResult { variables, rows })
+}
+
+/// The index of the first node before the given one that has the same
+/// variable, which the node then has to be bound to as well
+fn bound_index(node_patterns: &[&NodePattern], node_index: usize) -> Option<usize> {
+    let variable = node_patterns[node_index].variable.as_ref()?;
+    node_patterns[..node_index]
+        .iter()
+        .position(|previous| previous.variable.as_ref() == Some(variable))
+}
+
+fn resolve_node_id(node: &NodePattern) -> ExternResult<Option<NodeId>> {
+    let Some(id) = &node.id else {
+        return Ok(None);
+    };
+    let node_id = match (&node.kind, id) {
+        (NodeKind::Agent, NodeIdPattern::Me) => NodeId::Agent(agent_info()?.agent_initial_pubkey),
+        (NodeKind::Anchor, NodeIdPattern::Literal(anchor)) => NodeId::Anchor(anchor.clone()),
+        (NodeKind::Agent, NodeIdPattern::Literal(agent)) => NodeId::Agent(
+            AgentPubKeyB64::from_b64_str(agent)
+                .map_err(|e| wasm_error!(WasmErrorInner::Guest(format!("Invalid agent: {e}"))))?
+                .into(),
+        ),
+        (NodeKind::Thing, NodeIdPattern::Literal(thing_id)) => NodeId::Thing(
+            ActionHashB64::from_b64_str(thing_id)
+                .map_err(|e| wasm_error!(WasmErrorInner::Guest(format!("Invalid Thing id: {e}"))))?
+                .into(),
+        ),
+        _ => {
+            return Err(wasm_error!(WasmErrorInner::Guest(format!(
+                "Invalid id for a node of kind {:?}.",
+                node.kind
+            ))))
+        }
+    };
+    Ok(Some(node_id))
+}
+
+/// Gets the neighbours of each of the given nodes that match the edge and
+/// node kind. The links of all nodes are fetched in a single host call, and
+/// for incoming edges the links in the other direction in a second one.
+fn get_matching_neighbours(
+    node_ids: &[NodeId],
+    edge: &EdgePattern,
+    kind: &NodeKind,
+) -> ExternResult<Vec<Vec<NodeId>>> {
+    let link_types = match kind {
+        NodeKind::Anchor => vec![LinkTypes::ToAnchor],
+        NodeKind::Agent => vec![LinkTypes::ToAgent],
+        NodeKind::Thing => vec![LinkTypes::ToThing],
+        NodeKind::Any => vec![LinkTypes::ToThing, LinkTypes::ToAnchor, LinkTypes::ToAgent],
+    };
+    let queries: Vec<(NodeId, LinkTypes)> = node_ids
+        .iter()
+        .flat_map(|node_id| {
+            link_types
+                .iter()
+                .map(move |link_type| (node_id.clone(), *link_type))
+        })
+        .collect();
+    let neighbours: Vec<Vec<NodeId>> = get_linked_node_ids(&queries, &edge.tag_filter)?
+        .chunks(link_types.len())
+        .map(|chunk| chunk.concat())
+        .collect();
+    if !edge.incoming {
+        return Ok(neighbours);
+    }
+
+    // Only keep the neighbours that have a matching link to the node
+    let mut check_queries: Vec<(NodeId, LinkTypes)> = Vec::new();
+    for (node_id, node_neighbours) in node_ids.iter().zip(&neighbours) {
+        for neighbour in node_neighbours {
+            let query = (neighbour.clone(), link_type_for_node_id(node_id));
+            if !check_queries.contains(&query) {
+                check_queries.push(query);
+            }
+        }
+    }
+    let matching_links: HashSet<(NodeId, NodeId)> = check_queries
+        .iter()
+        .zip(get_linked_node_ids(&check_queries, &edge.tag_filter)?)
+        .flat_map(|((src, _), dsts)| dsts.into_iter().map(move |dst| (src.clone(), dst)))
+        .collect();
+    Ok(node_ids
+        .iter()
+        .zip(neighbours)
+        .map(|(node_id, node_neighbours)| {
+            node_neighbours
+                .into_iter()
+                .filter(|neighbour| matching_links.contains(&(neighbour.clone(), node_id.clone())))
+                .collect()
+        })
+        .collect())
+}
+
+/// Gets the ids of the nodes linked from each node with the given link type
+/// that match the filter, all in a single host call
+fn get_linked_node_ids(
+    queries: &[(NodeId, LinkTypes)],
+    tag_filter: &Option<TagFilter>,
+) -> ExternResult<Vec<Vec<NodeId>>> {
+    let tag_prefix = match tag_filter {
+        Some(TagFilter::Exact(tag)) | Some(TagFilter::Prefix(tag)) => Some(tag.clone()),
+        None => None,
+    };
+    let inputs: Vec<GetLinkedNodesInput> = queries
+        .iter()
+        .map(|(node_id, _)| GetLinkedNodesInput {
+            tag_prefix: tag_prefix.clone(),
+            ..node_id.clone().into()
+        })
+        .collect();
+    let links = get_links_from_nodes(
+        &inputs
+            .iter()
+            .zip(queries)
+            .map(|(input, (_, link_type))| (input, *link_type))
+            .collect::<Vec<(&GetLinkedNodesInput, LinkTypes)>>(),
+    )?;
+    Ok(links
+        .into_iter()
+        .map(|links| {
+            links
+                .into_iter()
+                .filter(|(_, link_tag_content)| match tag_filter {
+                    Some(TagFilter::Exact(tag)) => link_tag_content.tag.as_ref() == Some(tag),
+                    _ => true,
+                })
+                .filter_map(|(link, link_tag_content)| node_id_from_link(link, &link_tag_content))
+                .collect()
+        })
+        .collect())
+}
+
+struct QueryParser<'a> {
+    query: &'a str,
+    pos: usize,
+}
+
+impl<'a> QueryParser<'a> {
+    fn new(query: &'a str) -> Self {
+        QueryParser { query, pos: 0 }
+    }
+
+    fn parse(mut self) -> ExternResult<GraphPattern> {
+        let start = self.parse_node()?;
+        let mut steps = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.pos == self.query.len() {
+                break;
+            }
+            let edge = self.parse_edge()?;
+            let node = self.parse_node()?;
+            steps.push((edge, node));
+        }
+        Ok(GraphPattern { start, steps })
+    }
+
+    fn parse_node(&mut self) -> ExternResult<NodePattern> {
+        self.expect("(")?;
+        let first = self.parse_ident()?;
+        self.skip_whitespace();
+        let (variable, kind) = match self.eat(":") {
+            true => (Some(first), self.parse_ident()?),
+            false => (None, first),
+        };
+        let kind = match kind.as_str() {
+            "anchor" => NodeKind::Anchor,
+            "agent" => NodeKind::Agent,
+            "thing" => NodeKind::Thing,
+            "node" => NodeKind::Any,
+            _ => return Err(self.error(&format!("unknown node kind '{kind}'"))),
+        };
+        self.skip_whitespace();
+        let id = match self.peek() {
+            Some('"') => Some(NodeIdPattern::Literal(self.parse_string()?)),
+            Some(')') => None,
+            _ => match self.parse_ident()?.as_str() {
+                "me" => Some(NodeIdPattern::Me),
+                other => return Err(self.error(&format!("unexpected '{other}'"))),
+            },
+        };
+        self.expect(")")?;
+        Ok(NodePattern { variable, kind, id })
+    }
+
+    fn parse_edge(&mut self) -> ExternResult<EdgePattern> {
+        let incoming = self.eat("<-[");
+        if !incoming {
+            self.expect("-[")?;
+        }
+        self.skip_whitespace();
+        let tag_filter = match self.peek() {
+            Some(']') => None,
+            _ => {
+                let filter = self.parse_ident()?;
+                self.skip_whitespace();
+                let tag = self.parse_string()?.into_bytes();
+                match filter.as_str() {
+                    "tag" => Some(TagFilter::Exact(tag)),
+                    "prefix" => Some(TagFilter::Prefix(tag)),
+                    _ => return Err(self.error(&format!("unknown edge filter '{filter}'"))),
+                }
+            }
+        };
+        self.expect("]")?;
+        match incoming {
+            true => self.expect("-")?,
+            false => self.expect("->")?,
+        }
+        Ok(EdgePattern {
+            incoming,
+            tag_filter,
+        })
+    }
+
+    fn parse_ident(&mut self) -> ExternResult<String> {
+        self.skip_whitespace();
+        let rest = &self.query[self.pos..];
+        let len = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error("expected an identifier"));
+        }
+        self.pos += len;
+        Ok(rest[..len].to_string())
+    }
+
+    fn parse_string(&mut self) -> ExternResult<String> {
+        self.expect("\"")?;
+        let mut string = String::new();
+        let mut chars = self.query[self.pos..].chars();
+        loop {
+            match chars.next() {
+                Some('"') => {
+                    self.pos += 1;
+                    return Ok(string);
+                }
+                Some('\\') => match chars.next() {
+                    Some(c @ ('"' | '\\')) => {
+                        self.pos += 2;
+                        string.push(c);
+                    }
+                    _ => return Err(self.error("invalid escape sequence")),
+                },
+                Some(c) => {
+                    self.pos += c.len_utf8();
+                    string.push(c);
+                }
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.query[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.query[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        if self.query[self.pos..].starts_with(token) {
+            self.pos += token.len();
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, token: &str) -> ExternResult<()> {
+        match self.eat(token) {
+            true => Ok(()),
+            false => Err(self.error(&format!("expected '{token}'"))),
+        }
+    }
+
+    fn error(&self, message: &str) -> WasmError {
+        wasm_error!(WasmErrorInner::Guest(format!(
+            "Invalid query at position {}: {message}",
+            self.pos
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(query: &str) -> GraphPattern {
+        QueryParser::new(query).parse().unwrap()
+    }
+
+    fn literal(node: &NodePattern) -> &str {
+        match &node.id {
+            Some(NodeIdPattern::Literal(literal)) => literal,
+            other => panic!("expected a literal id, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parses_a_chain_of_nodes_and_edges() {
+        let pattern =
+            parse(r#"(anchor "projects")-[tag "task"]->(t:thing)-[prefix "assign"]->(agent me)"#);
+
+        assert_eq!(pattern.start.kind, NodeKind::Anchor);
+        assert_eq!(pattern.start.variable, None);
+        assert_eq!(literal(&pattern.start), "projects");
+        assert_eq!(pattern.steps.len(), 2);
+
+        let (edge, node) = &pattern.steps[0];
+        assert!(!edge.incoming);
+        assert!(matches!(&edge.tag_filter, Some(TagFilter::Exact(tag)) if tag == b"task"));
+        assert_eq!(node.variable.as_deref(), Some("t"));
+        assert_eq!(node.kind, NodeKind::Thing);
+        assert!(node.id.is_none());
+
+        let (edge, node) = &pattern.steps[1];
+        assert!(matches!(&edge.tag_filter, Some(TagFilter::Prefix(tag)) if tag == b"assign"));
+        assert_eq!(node.kind, NodeKind::Agent);
+        assert!(matches!(node.id, Some(NodeIdPattern::Me)));
+    }
+
+    #[test]
+    fn parses_whitespace_between_tokens() {
+        let pattern = parse(r#" ( p : anchor "a" ) -[ ]-> ( node ) "#);
+
+        assert_eq!(pattern.start.variable.as_deref(), Some("p"));
+        assert_eq!(literal(&pattern.start), "a");
+        assert!(pattern.steps[0].0.tag_filter.is_none());
+        assert_eq!(pattern.steps[0].1.kind, NodeKind::Any);
+    }
+
+    #[test]
+    fn parses_escapes_in_strings() {
+        let pattern = parse(r#"(anchor "say \"hi\" \\ ünïcode")-[tag "a\"b"]->(node)"#);
+
+        assert_eq!(literal(&pattern.start), r#"say "hi" \ ünïcode"#);
+        assert!(
+            matches!(&pattern.steps[0].0.tag_filter, Some(TagFilter::Exact(tag)) if tag == br#"a"b"#)
+        );
+    }
+
+    #[test]
+    fn parses_incoming_edges() {
+        let pattern = parse(r#"(thing "id")<-[tag "task"]-(p:anchor)-[]->(node)"#);
+
+        assert!(pattern.steps[0].0.incoming);
+        assert_eq!(pattern.steps[0].1.kind, NodeKind::Anchor);
+        assert!(!pattern.steps[1].0.incoming);
+    }
+
+    #[test]
+    fn binds_repeated_variables_to_their_first_node() {
+        let pattern =
+            parse(r#"(a:anchor "x")-[]->(t:thing)-[]->(a:anchor)<-[]-(t:thing)-[]->(node)"#);
+        let node_patterns: Vec<&NodePattern> = std::iter::once(&pattern.start)
+            .chain(pattern.steps.iter().map(|(_, node)| node))
+            .collect();
+
+        assert_eq!(bound_index(&node_patterns, 0), None);
+        assert_eq!(bound_index(&node_patterns, 1), None);
+        assert_eq!(bound_index(&node_patterns, 2), Some(0));
+        assert_eq!(bound_index(&node_patterns, 3), Some(1));
+        assert_eq!(bound_index(&node_patterns, 4), None);
+    }
+
+    #[test]
+    fn rejects_malformed_queries() {
+        for query in [
+            "",
+            "anchor",
+            r#"(anchor "a""#,
+            r#"(anchor "a)"#,
+            r#"(anchor "\n")"#,
+            r#"(anchor "\")"#,
+            "(unknown)",
+            "(a:)",
+            "(agent you)",
+            r#"(anchor "a") (thing)"#,
+            r#"(anchor "a")-[tag "t"]-(thing)"#,
+            r#"(anchor "a")<-[tag "t"]->(thing)"#,
+            r#"(anchor "a")-[label "t"]->(thing)"#,
+            r#"(anchor "a")-[tag t]->(thing)"#,
+            r#"(anchor "a")-[]->"#,
+        ] {
+            let err = QueryParser::new(query).parse().unwrap_err();
+            assert!(
+                format!("{err:?}").contains("Invalid query at position"),
+                "{query}: {err:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use crate::api::{link_from_create_link_action, link_type_for_node_id, linkable_hash_from_node_id};
+use crate::batch::{get_details_many, get_link_details_many};
+use crate::NodeLinkMeta;
+use generic_zome_integrity::*;
+use hdk::prelude::*;
+use std::collections::HashMap;
+
+/// Crontab of the scheduled repair: every hour
+#[cfg(feature = "scheduled-repair")]
+const REPAIR_GRAPH_SCHEDULE: &str = "0 0 * * * * *";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RepairGraphInput {
+    pub node_ids: Vec<NodeId>,
+    /// Whether to delete the broken links that the calling agent authored.
+    /// Otherwise all issues are only reported.
+    pub fix: bool,
+    /// Defaults to Network
+    pub get_strategy: Option<GetStrategy>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type")]
+pub enum GraphIssueKind {
+    /// A link of a bidirectional pair whose backlink has been deleted or
+    /// cannot be found
+    MissingBacklink,
+    /// The backlink of a bidirectional pair whose link has been deleted
+    OrphanedBacklink,
+    /// A link pointing to a Thing that has been deleted
+    LinkToDeletedThing,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GraphIssue {
+    pub kind: GraphIssueKind,
+    pub link: NodeLinkMeta,
+    /// Whether the broken link has been deleted. Only links authored by the
+    /// calling agent are deleted, and only if the issue is certain: a
+    /// backlink that cannot be found may just not have reached this agent
+    /// yet.
+    pub repaired: bool,
+}
+
+/// A link together with its decoded tag and whether it has been deleted
+struct LinkState {
+    link: Link,
+    meta_tag: LinkTagContent,
+    deleted: bool,
+}
+
+/// Checks the links from the given nodes for broken bidirectional pairs and
+/// links to deleted Things. Such links are left behind if one side of a
+/// pair is deleted without following its backlink_action_hash.
+#[hdk_extern]
+pub fn repair_graph(input: RepairGraphInput) -> ExternResult<Vec<GraphIssue>> {
+    let strategy = input.get_strategy.unwrap_or(GetStrategy::Network);
+    let me = agent_info()?.agent_initial_pubkey;
+
+    // 1. Get the live links from all nodes
+    let queries: Vec<(NodeId, LinkTypes)> = input
+        .node_ids
+        .iter()
+        .flat_map(|node_id| {
+            [LinkTypes::ToThing, LinkTypes::ToAnchor, LinkTypes::ToAgent]
+                .map(|link_type| (node_id.clone(), link_type))
+        })
+        .collect();
+    let live_links: Vec<(NodeId, LinkState)> = queries
+        .iter()
+        .map(|(node_id, _)| node_id.clone())
+        .zip(get_link_states(&queries, strategy)?)
+        .flat_map(|(node_id, links)| {
+            links
+                .into_iter()
+                .filter(|l| !l.deleted)
+                .map(move |l| (node_id.clone(), l))
+        })
+        .collect();
+
+    // 2. Find out which of the linked Things have been deleted
+    let mut thing_ids: Vec<ActionHash> = Vec::new();
+    for (_, l) in &live_links {
+        if let NodeId::Thing(thing_id) = &l.meta_tag.target_node_id {
+            if !thing_ids.contains(thing_id) {
+                thing_ids.push(thing_id.clone());
+            }
+        }
+    }
+    let deleted_thing_ids: HashSet<ActionHash> = thing_ids
+        .iter()
+        .zip(get_details_many(
+            thing_ids.iter().cloned().map(Into::into).collect(),
+            strategy,
+        )?)
+        .filter_map(|(thing_id, details)| match details {
+            Some(Details::Record(details)) if !details.deletes.is_empty() => Some(thing_id.clone()),
+            _ => None,
+        })
+        .collect();
+
+    // 3. Get the links pointing back from the linked nodes, which contain
+    // both the backlinks of the links and the links of the backlinks
+    let mut counterpart_queries: Vec<(NodeId, LinkTypes)> = Vec::new();
+    for (src, l) in &live_links {
+        let query = (
+            l.meta_tag.target_node_id.clone(),
+            link_type_for_node_id(src),
+        );
+        if !counterpart_queries.contains(&query) {
+            counterpart_queries.push(query);
+        }
+    }
+    let counterparts: Vec<LinkState> = get_link_states(&counterpart_queries, strategy)?
+        .into_iter()
+        .flatten()
+        .collect();
+    // Whether the link with the given create action hash has been deleted
+    let link_deleted: HashMap<ActionHash, bool> = counterparts
+        .iter()
+        .map(|l| (l.link.create_link_hash.clone(), l.deleted))
+        .collect();
+    // Whether all links with the given backlink have been deleted
+    let mut backlink_orphaned: HashMap<ActionHash, bool> = HashMap::new();
+    for l in &counterparts {
+        if let Some(backlink_action_hash) = &l.meta_tag.backlink_action_hash {
+            let orphaned = backlink_orphaned
+                .entry(backlink_action_hash.clone())
+                .or_insert(true);
+            *orphaned = *orphaned && l.deleted;
+        }
+    }
+
+    // 4. Classify and repair
+    let mut issues: Vec<GraphIssue> = Vec::new();
+    for (src, l) in live_links {
+        let (kind, certain) = match (&l.meta_tag.target_node_id, &l.meta_tag.backlink_action_hash) {
+            (NodeId::Thing(thing_id), _) if deleted_thing_ids.contains(thing_id) => {
+                (GraphIssueKind::LinkToDeletedThing, true)
+            }
+            (_, Some(backlink_action_hash)) => match link_deleted.get(backlink_action_hash) {
+                Some(false) => continue,
+                Some(true) => (GraphIssueKind::MissingBacklink, true),
+                None => (GraphIssueKind::MissingBacklink, false),
+            },
+            (_, None) => match backlink_orphaned.get(&l.link.create_link_hash) {
+                Some(true) => (GraphIssueKind::OrphanedBacklink, true),
+                _ => continue,
+            },
+        };
+        let repaired = input.fix && certain && l.link.author == me;
+        if repaired {
+            delete_link(l.link.create_link_hash.clone())?;
+        }
+        issues.push(GraphIssue {
+            kind,
+            link: NodeLinkMeta {
+                src,
+                dst: l.meta_tag.target_node_id.clone(),
+                meta_tag: l.meta_tag,
+                create_action_hash: l.link.create_link_hash,
+            },
+            repaired,
+        });
+    }
+
+    Ok(issues)
+}
+
+/// Gets all links ever created for each of the queries in a single host
+/// call. Links whose tag cannot be decoded are skipped.
+fn get_link_states(
+    queries: &[(NodeId, LinkTypes)],
+    strategy: GetStrategy,
+) -> ExternResult<Vec<Vec<LinkState>>> {
+    let link_details = get_link_details_many(
+        queries
+            .iter()
+            .map(|(node_id, link_type)| {
+                Ok(GetLinksInputBuilder::try_new(
+                    linkable_hash_from_node_id(node_id.clone())?,
+                    *link_type,
+                )?
+                .get_options(strategy)
+                .build())
+            })
+            .collect::<ExternResult<Vec<GetLinksInput>>>()?,
+    )?;
+    Ok(link_details
+        .into_iter()
+        .map(|details| {
+            details
+                .into_inner()
+                .into_iter()
+                .filter_map(|(create, deletes)| {
+                    let link = link_from_create_link_action(&create)?;
+                    let meta_tag = deserialize_link_tag(link.tag.0.clone()).ok()?;
+                    Some(LinkState {
+                        link,
+                        meta_tag,
+                        deleted: !deletes.is_empty(),
+                    })
+                })
+                .collect()
+        })
+        .collect())
+}
+
+/// Repairs the links from the calling agent and from all Things they created
+/// and have not deleted. Scheduled in `init` if the zome is built with the
+/// `scheduled-repair` feature, which requires a conductor with unstable
+/// functions enabled.
+#[cfg(feature = "scheduled-repair")]
+#[hdk_extern(infallible)]
+pub fn scheduled_repair_graph(_: Option<Schedule>) -> Option<Schedule> {
+    if let Err(err) = repair_own_nodes() {
+        error!("Failed to repair the graph: {:?}", err);
+    }
+    Some(Schedule::Persisted(REPAIR_GRAPH_SCHEDULE.into()))
+}
+
+#[cfg(feature = "scheduled-repair")]
+fn repair_own_nodes() -> ExternResult<Vec<GraphIssue>> {
+    let mut node_ids = vec![NodeId::Agent(agent_info()?.agent_initial_pubkey)];
+    node_ids.extend(
+        crate::chain::query_my_live_things()?
+            .into_iter()
+            .map(|record| NodeId::Thing(record.action_address().clone())),
+    );
+    repair_graph(RepairGraphInput {
+        node_ids,
+        fix: true,
+        get_strategy: None,
+    })
+}
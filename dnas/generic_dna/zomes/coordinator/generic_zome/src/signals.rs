@@ -0,0 +1,235 @@
+use crate::api::{
+    link_from_create_link_action, node_id_from_link, original_thing_record_to_thing,
+    thing_record_to_thing,
+};
+use crate::batch::get_links_many;
+use crate::trash::{trash_anchor, TRASH_ANCHOR};
+use crate::{NodeLink, NodeLinkMeta, SignalKind, Thing, SIMPLE_HOLOCHAIN_ALL_AGENTS};
+use generic_zome_integrity::*;
+use hdk::prelude::*;
+use std::collections::HashMap;
+
+/// Converts the actions committed by a zome call into signals. Every create,
+/// update and delete of a Thing gets its own signal while all created and
+/// all deleted links are collected into one signal each. Actions on other
+/// entry and link types are skipped.
+pub(crate) fn signals_for_actions(
+    committed_actions: &[SignedActionHashed],
+) -> ExternResult<Vec<SignalKind>> {
+    let thing_entry_type: EntryType = UnitEntryTypes::Thing.try_into()?;
+
+    let mut created_links: Vec<Link> = Vec::new();
+    let mut deleted_links: Vec<Link> = Vec::new();
+    for action in committed_actions {
+        match action.action() {
+            Action::CreateLink(_) => created_links.extend(link_from_create_link_action(action)),
+            Action::DeleteLink(delete_link) => {
+                if let Some(record) = get_local(delete_link.link_add_address.clone())? {
+                    deleted_links.extend(link_from_create_link_action(&record.signed_action));
+                }
+            }
+            _ => (),
+        }
+    }
+    let anchors = known_anchors(
+        &created_links
+            .iter()
+            .chain(deleted_links.iter())
+            .collect::<Vec<&Link>>(),
+    )?;
+
+    let mut signals: Vec<SignalKind> = Vec::new();
+    for action in committed_actions {
+        match action.action() {
+            Action::Create(create) if create.entry_type == thing_entry_type => {
+                if let Some(record) = get_local(action.action_address().clone())? {
+                    signals.push(SignalKind::ThingCreated {
+                        thing: original_thing_record_to_thing(record)?,
+                    });
+                }
+            }
+            Action::Update(update) if update.entry_type == thing_entry_type => {
+                // Clients look up updates via the ThingUpdates link, so an
+                // update is only signaled together with its link
+                let Some(update_link) = created_links.iter().find(|link| {
+                    is_thing_updates_link(link)
+                        && link.target == action.action_address().clone().into()
+                }) else {
+                    continue;
+                };
+                let (Some(original_record), Some(update_record)) = (
+                    get_local(update.original_action_address.clone())?,
+                    get_local(action.action_address().clone())?,
+                ) else {
+                    continue;
+                };
+                let original_thing = original_thing_record_to_thing(original_record)?;
+                signals.push(SignalKind::ThingUpdated {
+                    thing: Thing {
+                        id: original_thing.id.clone(),
+                        ..thing_record_to_thing(update_record, original_thing)?
+                    },
+                    update_action_hash: action.action_address().clone(),
+                    update_link_action_hash: update_link.create_link_hash.clone(),
+                });
+            }
+            Action::Delete(delete) => {
+                // Unlike get, get_details returns deleted records as well
+                let Some(Details::Record(deleted)) = get_details(
+                    delete.deletes_address.clone(),
+                    GetOptions {
+                        strategy: GetStrategy::Local,
+                    },
+                )?
+                else {
+                    continue;
+                };
+                if deleted.record.action().entry_type() == Some(&thing_entry_type) {
+                    signals.push(SignalKind::ThingDeleted {
+                        id: delete.deletes_address.clone(),
+                    });
+                }
+            }
+            _ => (),
+        }
+    }
+
+    let links: Vec<NodeLinkMeta> = created_links
+        .into_iter()
+        .filter_map(|link| node_link_meta(link, &anchors))
+        .collect();
+    if !links.is_empty() {
+        signals.push(SignalKind::LinksCreated { links });
+    }
+    let links: Vec<NodeLink> = deleted_links
+        .into_iter()
+        .filter_map(|link| node_link_meta(link, &anchors))
+        .map(|link| NodeLink {
+            src: link.src,
+            dst: link.dst,
+            tag: link.meta_tag.tag,
+            create_action_hash: link.create_action_hash,
+        })
+        .collect();
+    if !links.is_empty() {
+        signals.push(SignalKind::LinksDeleted { links });
+    }
+
+    Ok(signals)
+}
+
+/// Gets a record from the local databases only. The records of committed
+/// actions and the ones they refer to have just been read or written by the
+/// zome call, so there is no need to go to the network.
+fn get_local(action_hash: ActionHash) -> ExternResult<Option<Record>> {
+    get(
+        action_hash,
+        GetOptions {
+            strategy: GetStrategy::Local,
+        },
+    )
+}
+
+fn is_thing_updates_link(link: &Link) -> bool {
+    matches!(
+        LinkTypes::from_type(link.zome_index, link.link_type),
+        Ok(Some(LinkTypes::ThingUpdates))
+    )
+}
+
+/// Anchors can't be derived from their path entry hash. Link tags name the
+/// anchor at the base of their link from version 1 on, so for links with
+/// version 0 tags this collects the anchors that their base may refer to: the
+/// anchors that this zome links from itself, the ones named in the link tags,
+/// which include the backlinks of bidirectional links from anchors, and for
+/// other backlinks the anchor named in the tag of their forward link.
+fn known_anchors(links: &[&Link]) -> ExternResult<HashMap<AnyLinkableHash, String>> {
+    let mut anchors: HashMap<AnyLinkableHash, String> = HashMap::new();
+    let mut add_anchor = |anchor: String| -> ExternResult<()> {
+        anchors.insert(Path::from(anchor.clone()).path_entry_hash()?.into(), anchor);
+        Ok(())
+    };
+    add_anchor(SIMPLE_HOLOCHAIN_ALL_AGENTS.to_string())?;
+    add_anchor(TRASH_ANCHOR.to_string())?;
+    if let NodeId::Anchor(anchor) = trash_anchor(false)? {
+        add_anchor(anchor)?;
+    }
+    let mut maybe_backlinks: Vec<&Link> = Vec::new();
+    for link in links {
+        let Ok(versioned_link_tag) = VersionedLinkTag::decode(&link.tag.0) else {
+            continue;
+        };
+        if versioned_link_tag.version() > 0 {
+            continue;
+        }
+        let link_tag_content = LinkTagContent::from(versioned_link_tag);
+        if let NodeId::Anchor(anchor) = link_tag_content.target_node_id {
+            add_anchor(anchor)?;
+        }
+        if link_tag_content.backlink_action_hash.is_none()
+            && link.base.clone().into_action_hash().is_none()
+        {
+            maybe_backlinks.push(link);
+        }
+    }
+
+    let maybe_backlinks: Vec<&Link> = maybe_backlinks
+        .into_iter()
+        .filter(|link| !anchors.contains_key(&link.base))
+        .collect();
+    let forward_links = get_links_many(
+        maybe_backlinks
+            .iter()
+            .map(|link| {
+                Ok(
+                    GetLinksInputBuilder::try_new(link.target.clone(), LinkTypes::ToAnchor)?
+                        .get_options(GetStrategy::Local)
+                        .build(),
+                )
+            })
+            .collect::<ExternResult<Vec<GetLinksInput>>>()?,
+    )?;
+    for (backlink, forward_links) in maybe_backlinks.into_iter().zip(forward_links) {
+        for forward_link in forward_links {
+            if let Ok(LinkTagContent {
+                target_node_id: NodeId::Anchor(anchor),
+                backlink_action_hash: Some(backlink_action_hash),
+                ..
+            }) = deserialize_link_tag(forward_link.tag.0)
+            {
+                if backlink_action_hash == backlink.create_link_hash {
+                    anchors.insert(backlink.base.clone(), anchor);
+                }
+            }
+        }
+    }
+    Ok(anchors)
+}
+
+/// Derives the nodes at both ends of a ToThing, ToAgent or ToAnchor link.
+/// None for other link types, undecodable tags and links with version 0
+/// tags from an entry hash that isn't a known anchor, since that
+/// may be an agent as well as an anchor.
+fn node_link_meta(link: Link, anchors: &HashMap<AnyLinkableHash, String>) -> Option<NodeLinkMeta> {
+    let versioned_link_tag = VersionedLinkTag::decode(&link.tag.0).ok()?;
+    let version = versioned_link_tag.version();
+    let meta_tag = LinkTagContent::from(versioned_link_tag);
+    let src = match (
+        &meta_tag.source_anchor,
+        link.base.clone().into_action_hash(),
+    ) {
+        (Some(anchor), _) => NodeId::Anchor(anchor.clone()),
+        (None, Some(thing_id)) => NodeId::Thing(thing_id),
+        // Agent keys are linkable as entry hashes just like anchors
+        (None, None) if version > 0 => NodeId::Agent(link.base.clone().into_agent_pub_key()?),
+        (None, None) => NodeId::Anchor(anchors.get(&link.base)?.clone()),
+    };
+    let create_action_hash = link.create_link_hash.clone();
+    let dst = node_id_from_link(link, &meta_tag)?;
+    Some(NodeLinkMeta {
+        src,
+        dst,
+        meta_tag,
+        create_action_hash,
+    })
+}
@@ -0,0 +1,140 @@
+use std::cmp::Ordering;
+
+use crate::api::{LinkCursor, SortOrder};
+use crate::Thing;
+use generic_zome_integrity::*;
+use hdk::prelude::*;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type")]
+pub enum SortKey {
+    /// When the linked Thing was originally created
+    CreatedAt,
+    /// When the linked Thing was last updated, or created if it has never
+    /// been updated
+    UpdatedAt,
+    /// When the link was created
+    LinkTimestamp,
+    /// The public key of the creator of the linked Thing
+    Creator,
+    /// The user tag interpreted as a string, compared byte by byte after
+    /// skipping the first `skip` bytes (e.g. a common tag prefix)
+    TagString { skip: usize },
+    /// The user tag interpreted as a UTF-8 encoded decimal number after
+    /// skipping the first `skip` bytes
+    TagNumber { skip: usize },
+    /// The position of the link in an ordered collection
+    Position,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SortBy {
+    pub key: SortKey,
+    pub order: SortOrder,
+}
+
+#[derive(PartialEq, PartialOrd)]
+enum SortValue {
+    Timestamp(Timestamp),
+    Agent(AgentPubKey),
+    Number(f64),
+    Bytes(Vec<u8>),
+}
+
+/// A linked node together with the link pointing to it, ready to be sorted
+pub struct SortableLinkedNode<T> {
+    pub link: Link,
+    pub link_tag_content: LinkTagContent,
+    /// The latest version of the linked Thing if the node is a Thing
+    pub thing: Option<Thing>,
+    pub node: T,
+}
+
+fn sort_value<T>(item: &SortableLinkedNode<T>, key: &SortKey) -> Option<SortValue> {
+    match key {
+        SortKey::CreatedAt => match &item.thing {
+            Some(thing) => Some(SortValue::Timestamp(thing.created_at)),
+            None => item
+                .link_tag_content
+                .thing_created_at
+                .map(SortValue::Timestamp),
+        },
+        SortKey::UpdatedAt => item
+            .thing
+            .as_ref()
+            .map(|thing| SortValue::Timestamp(thing.updated_at.unwrap_or(thing.created_at))),
+        SortKey::LinkTimestamp => Some(SortValue::Timestamp(item.link.timestamp)),
+        SortKey::Creator => match &item.thing {
+            Some(thing) => Some(SortValue::Agent(thing.creator.clone())),
+            None => item
+                .link_tag_content
+                .thing_created_by
+                .clone()
+                .map(SortValue::Agent),
+        },
+        SortKey::TagString { skip } => item
+            .link_tag_content
+            .tag
+            .as_ref()
+            .and_then(|tag| tag.get(*skip..))
+            .map(|bytes| SortValue::Bytes(bytes.to_vec())),
+        SortKey::TagNumber { skip } => item
+            .link_tag_content
+            .tag
+            .as_ref()
+            .and_then(|tag| tag.get(*skip..))
+            .and_then(|bytes| std::str::from_utf8(bytes).ok())
+            .and_then(|s| s.trim().parse::<f64>().ok())
+            .filter(|n| !n.is_nan())
+            .map(SortValue::Number),
+        SortKey::Position => item
+            .link_tag_content
+            .position
+            .as_ref()
+            .map(|position| SortValue::Bytes(position.as_bytes().to_vec())),
+    }
+}
+
+/// Sorts linked nodes by the given key. Nodes without a value for the key
+/// (e.g. Agents when sorting by `UpdatedAt` or links without a numeric tag
+/// when sorting by `TagNumber`) come last in either order. Ties are broken
+/// by link timestamp and create link hash so that all agents get the same
+/// order for the same set of links.
+pub fn sort_linked_nodes<T>(
+    items: Vec<SortableLinkedNode<T>>,
+    sort_by: &SortBy,
+) -> Vec<SortableLinkedNode<T>> {
+    let mut keyed_items: Vec<_> = items
+        .into_iter()
+        .map(|item| {
+            (
+                sort_value(&item, &sort_by.key),
+                LinkCursor::from(&item.link),
+                item,
+            )
+        })
+        .collect();
+    keyed_items.sort_by(|(a, cursor_a, _), (b, cursor_b, _)| {
+        compare_sort_values(a, b, &sort_by.order).then_with(|| cursor_a.cmp(cursor_b))
+    });
+    keyed_items.into_iter().map(|(_, _, item)| item).collect()
+}
+
+fn compare_sort_values(
+    a: &Option<SortValue>,
+    b: &Option<SortValue>,
+    order: &SortOrder,
+) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => {
+            let ordering = a.partial_cmp(b).unwrap_or(Ordering::Equal);
+            match order {
+                SortOrder::Ascending => ordering,
+                SortOrder::Descending => ordering.reverse(),
+            }
+        }
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
@@ -0,0 +1,73 @@
+use crate::api::{
+    create_link_from_node_by_id, get_links_from_node, GetLinkedNodesInput, LinkDirection, LinkInput,
+};
+use crate::NodeLinkMeta;
+use generic_zome_integrity::*;
+use hdk::prelude::*;
+
+/// User tag of the links from a node to the agents subscribed to it
+pub const SUBSCRIBER_TAG: &[u8] = b"__subscriber";
+
+/// Subscribes the calling agent to the changes of a node. Subscribers get
+/// remote signals whenever the node or its links change. Subscribing twice
+/// returns the existing subscription.
+#[hdk_extern]
+pub fn subscribe(node_id: NodeId) -> ExternResult<NodeLinkMeta> {
+    let (node_link, _) = create_link_from_node_by_id(
+        node_id,
+        LinkInput {
+            direction: LinkDirection::To,
+            node_id: NodeId::Agent(agent_info()?.agent_initial_pubkey),
+            tag: Some(SUBSCRIBER_TAG.to_vec()),
+            dedupe: true,
+            position: None,
+            expires_at: None,
+        },
+    )?;
+    Ok(node_link)
+}
+
+/// Removes all subscriptions of the calling agent to a node
+#[hdk_extern]
+pub fn unsubscribe(node_id: NodeId) -> ExternResult<()> {
+    let me = agent_info()?.agent_initial_pubkey;
+    for link in get_subscription_links(&node_id)? {
+        if link.author == me && link.target == me.clone().into() {
+            delete_link(link.create_link_hash)?;
+        }
+    }
+    Ok(())
+}
+
+/// Gets the agents subscribed to a node
+#[hdk_extern]
+pub fn get_subscribers(node_id: NodeId) -> ExternResult<Vec<AgentPubKey>> {
+    let mut subscribers: Vec<AgentPubKey> = Vec::new();
+    for link in get_subscription_links(&node_id)? {
+        if let Some(agent) = link.target.into_agent_pub_key() {
+            if !subscribers.contains(&agent) {
+                subscribers.push(agent);
+            }
+        }
+    }
+    Ok(subscribers)
+}
+
+pub(crate) fn is_subscription_link(link_tag_content: &LinkTagContent) -> bool {
+    link_tag_content.tag.as_deref() == Some(SUBSCRIBER_TAG)
+}
+
+/// Gets the links from a node to the agents subscribed to it
+fn get_subscription_links(node_id: &NodeId) -> ExternResult<Vec<Link>> {
+    Ok(get_links_from_node(
+        &GetLinkedNodesInput {
+            tag_prefix: Some(SUBSCRIBER_TAG.to_vec()),
+            ..node_id.clone().into()
+        },
+        LinkTypes::ToAgent,
+    )?
+    .into_iter()
+    .filter(|(_, c)| is_subscription_link(c))
+    .map(|(link, _)| link)
+    .collect())
+}
@@ -0,0 +1,199 @@
+use crate::api::{
+    is_hidden_link, link_from_create_link_action, linkable_hash_from_node_id, node_id_from_link,
+};
+use crate::batch::{get_details_many, get_latest_things_batched, get_link_details_many};
+use crate::{NodeLinkMeta, Thing};
+use generic_zome_integrity::*;
+use hdk::prelude::*;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GetNodeChangesSinceInput {
+    pub node_ids: Vec<NodeId>,
+    /// Only changes with a timestamp after this one are returned. Pass the
+    /// high_water_mark of the previous call.
+    pub since: Timestamp,
+    /// Defaults to Network
+    pub get_strategy: Option<GetStrategy>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NodeChanges {
+    pub node_id: NodeId,
+    /// Links from the node that have been created after `since` and are
+    /// not deleted
+    pub links_created: Vec<NodeLinkMeta>,
+    /// Links from the node that have been deleted after `since`
+    pub links_deleted: Vec<NodeLinkMeta>,
+    /// The latest version of the Thing if it has been updated after `since`.
+    /// Only set for Thing nodes.
+    pub thing_updated: Option<Thing>,
+    /// Whether the Thing has been deleted after `since`. Always false for
+    /// Agent and Anchor nodes.
+    pub thing_deleted: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NodeChangesSince {
+    /// The changes of all nodes that changed. Nodes without changes are left
+    /// out.
+    pub changes: Vec<NodeChanges>,
+    /// The latest timestamp of all returned changes, or `since` if there are
+    /// none. Changes that only reach this agent later but carry an older
+    /// timestamp are missed, so clients should still do a full poll every
+    /// now and then.
+    pub high_water_mark: Timestamp,
+}
+
+/// Gets the changes of the given nodes since the given time, i.e. the links
+/// created and deleted as well as the Things updated or deleted. Uses one
+/// host call for the link details of all nodes, one for the details of all
+/// Things and one batch for the updated Things. Like the regular reads, the
+/// links to subscribers and trash anchors are left out.
+#[hdk_extern]
+pub fn get_node_changes_since(input: GetNodeChangesSinceInput) -> ExternResult<NodeChangesSince> {
+    let strategy = input.get_strategy.unwrap_or(GetStrategy::Network);
+    let since = input.since;
+    let mut high_water_mark = since;
+
+    let mut queries: Vec<(usize, LinkTypes)> = Vec::new();
+    for (i, node_id) in input.node_ids.iter().enumerate() {
+        queries.extend(
+            [LinkTypes::ToThing, LinkTypes::ToAnchor, LinkTypes::ToAgent].map(|lt| (i, lt)),
+        );
+        if let NodeId::Thing(_) = node_id {
+            queries.push((i, LinkTypes::ThingUpdates));
+        }
+    }
+    let link_details = get_link_details_many(
+        queries
+            .iter()
+            .map(|(i, link_type)| {
+                let base = linkable_hash_from_node_id(input.node_ids[*i].clone())?;
+                Ok(GetLinksInputBuilder::try_new(base, *link_type)?
+                    .get_options(strategy)
+                    .build())
+            })
+            .collect::<ExternResult<Vec<GetLinksInput>>>()?,
+    )?;
+
+    let mut changes: Vec<NodeChanges> = input
+        .node_ids
+        .iter()
+        .map(|node_id| NodeChanges {
+            node_id: node_id.clone(),
+            links_created: Vec::new(),
+            links_deleted: Vec::new(),
+            thing_updated: None,
+            thing_deleted: false,
+        })
+        .collect();
+    let mut updated = vec![false; input.node_ids.len()];
+
+    for ((i, link_type), details) in queries.into_iter().zip(link_details) {
+        for (create, deletes) in details.into_inner() {
+            let Some(link) = link_from_create_link_action(&create) else {
+                continue;
+            };
+            let deleted_at = deletes
+                .iter()
+                .map(|delete| delete.action().timestamp())
+                .max();
+            if let LinkTypes::ThingUpdates = link_type {
+                if deleted_at.is_none() && link.timestamp > since {
+                    updated[i] = true;
+                    high_water_mark = high_water_mark.max(link.timestamp);
+                }
+                continue;
+            }
+            let Ok(meta_tag) = deserialize_link_tag(link.tag.0.clone()) else {
+                continue;
+            };
+            if is_hidden_link(&meta_tag, None) {
+                continue;
+            }
+            let create_action_hash = link.create_link_hash.clone();
+            let Some(dst) = node_id_from_link(link.clone(), &meta_tag) else {
+                continue;
+            };
+            let node_link = NodeLinkMeta {
+                src: input.node_ids[i].clone(),
+                dst,
+                meta_tag,
+                create_action_hash,
+            };
+            match deleted_at {
+                Some(deleted_at) if deleted_at > since => {
+                    high_water_mark = high_water_mark.max(deleted_at);
+                    changes[i].links_deleted.push(node_link);
+                }
+                None if link.timestamp > since => {
+                    high_water_mark = high_water_mark.max(link.timestamp);
+                    changes[i].links_created.push(node_link);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    // Deletes of Things are only visible in the details of their original
+    // record
+    let thing_indices: Vec<(usize, ActionHash)> = input
+        .node_ids
+        .iter()
+        .enumerate()
+        .filter_map(|(i, node_id)| match node_id {
+            NodeId::Thing(thing_id) => Some((i, thing_id.clone())),
+            _ => None,
+        })
+        .collect();
+    let details = get_details_many(
+        thing_indices
+            .iter()
+            .map(|(_, thing_id)| thing_id.clone().into())
+            .collect(),
+        strategy,
+    )?;
+    for ((i, _), maybe_details) in thing_indices.iter().zip(details) {
+        let Some(Details::Record(record_details)) = maybe_details else {
+            continue;
+        };
+        if let Some(deleted_at) = record_details
+            .deletes
+            .iter()
+            .map(|delete| delete.action().timestamp())
+            .filter(|timestamp| *timestamp > since)
+            .max()
+        {
+            high_water_mark = high_water_mark.max(deleted_at);
+            changes[*i].thing_deleted = true;
+        }
+    }
+
+    let updated_things: Vec<(usize, ActionHash)> = thing_indices
+        .into_iter()
+        .filter(|(i, _)| updated[*i] && !changes[*i].thing_deleted)
+        .collect();
+    let things = get_latest_things_batched(
+        updated_things
+            .iter()
+            .map(|(_, thing_id)| thing_id.clone())
+            .collect(),
+        strategy,
+    )?;
+    for ((i, _), thing) in updated_things.into_iter().zip(things) {
+        changes[i].thing_updated = thing;
+    }
+
+    Ok(NodeChangesSince {
+        changes: changes
+            .into_iter()
+            .filter(|c| {
+                !c.links_created.is_empty()
+                    || !c.links_deleted.is_empty()
+                    || c.thing_updated.is_some()
+                    || c.thing_deleted
+            })
+            .collect(),
+        high_water_mark,
+    })
+}
@@ -0,0 +1,243 @@
+use crate::api::{
+    create_link_from_node_by_id, delete_thing, get_links_from_node, get_links_from_nodes,
+    DeleteThingInput, GetLinkedNodesInput, LinkDirection, LinkInput, NodeContent,
+};
+use crate::batch::get_latest_things_batched;
+use crate::{NodeLinkMeta, Thing};
+use generic_zome_integrity::*;
+use hdk::prelude::*;
+
+/// Anchor of the global trash. The trash of an agent is the anchor
+/// `TRASH_<agent pub key>`.
+pub const TRASH_ANCHOR: &str = "TRASH";
+
+/// User tag of the links between a trashed Thing and a trash anchor
+pub const TRASHED_TAG: &[u8] = b"__trashed";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TrashThingInput {
+    pub thing_id: ActionHash,
+    /// Whether to move the Thing to the global trash instead of the trash
+    /// of the calling agent
+    pub global: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TrashInput {
+    /// Whether to use the global trash instead of the trash of the calling
+    /// agent
+    pub global: bool,
+}
+
+/// Moves a Thing to the trash. Things in the global trash are left out by
+/// the reads of all agents and Things in the trash of an agent only by the
+/// reads of that agent, unless `include_trashed` is set, but they stay retrievable and can be
+/// restored with `restore_thing` until the trash is emptied.
+#[hdk_extern]
+pub fn trash_thing(input: TrashThingInput) -> ExternResult<NodeLinkMeta> {
+    let (node_link, _) = create_link_from_node_by_id(
+        NodeId::Thing(input.thing_id),
+        LinkInput {
+            direction: LinkDirection::Bidirectional,
+            node_id: trash_anchor(input.global)?,
+            tag: Some(TRASHED_TAG.to_vec()),
+            dedupe: true,
+            position: None,
+            expires_at: None,
+        },
+    )?;
+    Ok(node_link)
+}
+
+/// Takes a Thing out of the global trash and the trash of the calling agent.
+/// The trashes of other agents are left untouched.
+#[hdk_extern]
+pub fn restore_thing(thing_id: ActionHash) -> ExternResult<()> {
+    let thing_node = NodeId::Thing(thing_id);
+    let my_trash = trash_anchor(false)?;
+    for (link, link_tag_content) in get_trash_links(&thing_node)? {
+        if !is_hiding_trash_link(&link_tag_content, &my_trash) {
+            continue;
+        }
+        if let Some(backlink_action_hash) = link_tag_content.backlink_action_hash {
+            delete_link(backlink_action_hash)?;
+        }
+        delete_link(link.create_link_hash)?;
+    }
+    Ok(())
+}
+
+/// Gets the latest versions of the Things in the trash
+#[hdk_extern]
+pub fn get_trash(input: TrashInput) -> ExternResult<Vec<Thing>> {
+    let thing_ids = get_trashed_thing_ids(&trash_anchor(input.global)?, false)?;
+    Ok(get_latest_things_batched(thing_ids, GetStrategy::Network)?
+        .into_iter()
+        .flatten()
+        .collect())
+}
+
+/// Deletes the Things in the trash for good, including the backlinks of
+/// their bidirectional links and the links to them from their creator. For
+/// the global trash only the Things moved there by the calling agent are
+/// deleted. Returns the ids of the deleted Things.
+#[hdk_extern]
+pub fn empty_trash(input: TrashInput) -> ExternResult<Vec<ActionHash>> {
+    let thing_ids = get_trashed_thing_ids(&trash_anchor(input.global)?, true)?;
+    for thing_id in thing_ids.iter() {
+        delete_thing(DeleteThingInput {
+            thing_id: thing_id.clone(),
+            delete_backlinks: true,
+            delete_links_from_creator: true,
+            delete_links: None,
+        })?;
+    }
+    Ok(thing_ids)
+}
+
+/// For each Thing, whether it has been moved to the global trash or to the
+/// trash of the calling agent
+pub(crate) fn are_trashed(
+    thing_ids: &[ActionHash],
+    strategy: GetStrategy,
+) -> ExternResult<Vec<bool>> {
+    let inputs: Vec<GetLinkedNodesInput> = thing_ids
+        .iter()
+        .map(|thing_id| GetLinkedNodesInput {
+            tag_prefix: Some(TRASHED_TAG.to_vec()),
+            get_strategy: Some(strategy),
+            ..NodeId::Thing(thing_id.clone()).into()
+        })
+        .collect();
+    let my_trash = trash_anchor(false)?;
+    let links = get_links_from_nodes(
+        &inputs
+            .iter()
+            .map(|input| (input, LinkTypes::ToAnchor))
+            .collect::<Vec<(&GetLinkedNodesInput, LinkTypes)>>(),
+    )?;
+    Ok(links
+        .into_iter()
+        .map(|links| {
+            links
+                .iter()
+                .any(|(_, c)| is_hiding_trash_link(c, &my_trash))
+        })
+        .collect())
+}
+
+/// Leaves out the Things that are trashed for the calling agent
+pub(crate) fn without_trashed(
+    things: Vec<Option<Thing>>,
+    strategy: GetStrategy,
+) -> ExternResult<Vec<Option<Thing>>> {
+    let thing_ids: Vec<ActionHash> = things.iter().flatten().map(|t| t.id.clone()).collect();
+    let mut trashed = are_trashed(&thing_ids, strategy)?.into_iter();
+    Ok(things
+        .into_iter()
+        .map(|maybe_thing| maybe_thing.filter(|_| !trashed.next().unwrap_or_default()))
+        .collect())
+}
+
+/// Leaves out the Things among the nodes that are trashed for the calling agent
+pub(crate) fn without_trashed_nodes(
+    nodes: Vec<(NodeId, NodeContent)>,
+    strategy: GetStrategy,
+) -> ExternResult<Vec<(NodeId, NodeContent)>> {
+    let thing_ids: Vec<ActionHash> = nodes
+        .iter()
+        .filter_map(|(node_id, _)| match node_id {
+            NodeId::Thing(thing_id) => Some(thing_id.clone()),
+            _ => None,
+        })
+        .collect();
+    let mut trashed = are_trashed(&thing_ids, strategy)?.into_iter();
+    Ok(nodes
+        .into_iter()
+        .filter(|(node_id, _)| match node_id {
+            NodeId::Thing(_) => !trashed.next().unwrap_or_default(),
+            _ => true,
+        })
+        .collect())
+}
+
+/// Leaves out the links pointing to Things that are trashed for the calling
+/// agent
+pub(crate) fn without_trashed_links(
+    links: Vec<(Link, LinkTagContent)>,
+    strategy: GetStrategy,
+) -> ExternResult<Vec<(Link, LinkTagContent)>> {
+    let thing_ids: Vec<ActionHash> = links
+        .iter()
+        .filter_map(|(_, c)| match &c.target_node_id {
+            NodeId::Thing(thing_id) => Some(thing_id.clone()),
+            _ => None,
+        })
+        .collect();
+    let mut trashed = are_trashed(&thing_ids, strategy)?.into_iter();
+    Ok(links
+        .into_iter()
+        .filter(|(_, c)| match c.target_node_id {
+            NodeId::Thing(_) => !trashed.next().unwrap_or_default(),
+            _ => true,
+        })
+        .collect())
+}
+
+pub(crate) fn trash_anchor(global: bool) -> ExternResult<NodeId> {
+    Ok(NodeId::Anchor(match global {
+        true => TRASH_ANCHOR.to_string(),
+        false => format!(
+            "{TRASH_ANCHOR}_{}",
+            AgentPubKeyB64::from(agent_info()?.agent_initial_pubkey)
+        ),
+    }))
+}
+
+/// Whether a link moves a Thing to the global trash or to the trash of the
+/// agent whose trash anchor is given. The trashes of other agents don't hide
+/// the Thing.
+fn is_hiding_trash_link(link_tag_content: &LinkTagContent, my_trash: &NodeId) -> bool {
+    link_tag_content.tag.as_deref() == Some(TRASHED_TAG)
+        && (link_tag_content.target_node_id == NodeId::Anchor(TRASH_ANCHOR.to_string())
+            || &link_tag_content.target_node_id == my_trash)
+}
+
+fn is_trash_link(link_tag_content: &LinkTagContent) -> bool {
+    link_tag_content.tag.as_deref() == Some(TRASHED_TAG)
+        && match &link_tag_content.target_node_id {
+            NodeId::Anchor(anchor) => {
+                anchor == TRASH_ANCHOR || anchor.starts_with(&format!("{TRASH_ANCHOR}_"))
+            }
+            _ => false,
+        }
+}
+
+/// Gets the links from a Thing to the trash anchors it has been moved to
+fn get_trash_links(thing_node: &NodeId) -> ExternResult<Vec<(Link, LinkTagContent)>> {
+    Ok(get_links_from_node(
+        &GetLinkedNodesInput {
+            tag_prefix: Some(TRASHED_TAG.to_vec()),
+            ..thing_node.clone().into()
+        },
+        LinkTypes::ToAnchor,
+    )?
+    .into_iter()
+    .filter(|(_, c)| is_trash_link(c))
+    .collect())
+}
+
+fn get_trashed_thing_ids(trash: &NodeId, only_mine: bool) -> ExternResult<Vec<ActionHash>> {
+    let me = agent_info()?.agent_initial_pubkey;
+    Ok(get_links_from_node(
+        &GetLinkedNodesInput {
+            tag_prefix: Some(TRASHED_TAG.to_vec()),
+            ..trash.clone().into()
+        },
+        LinkTypes::ToThing,
+    )?
+    .into_iter()
+    .filter(|(link, c)| c.tag.as_deref() == Some(TRASHED_TAG) && (!only_mine || link.author == me))
+    .filter_map(|(link, _)| link.target.into_action_hash())
+    .collect())
+}
@@ -28,7 +28,7 @@ pub enum LinkTypes {
     ToAnchor,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
 #[serde(tag = "type", content = "id")]
 pub enum NodeId {
     Agent(AgentPubKey),
@@ -20,22 +20,688 @@ pub struct LinkTagContent {
     /// If it's a link pointing to a Thing then this contains the creator's
     /// public key
     pub thing_created_by: Option<AgentPubKey>,
+    /// If the link is part of an ordered collection, this contains its
+    /// fractional index key. Links are ordered by comparing the keys
+    /// lexicographically.
+    pub position: Option<String>,
+    /// If set, the link is treated as absent from this time on and gets
+    /// deleted by the scheduled cleanup of its author
+    pub expires_at: Option<Timestamp>,
+    /// If the base of the link is an anchor, this contains the anchor string
+    /// since it can't be derived from the anchor's path entry hash
+    pub source_anchor: Option<String>,
 }
 
+/// Version of the link tag layout written by `serialize_link_tag`
+pub const LINK_TAG_VERSION: u8 = 1;
+
+/// Maximum size of a link tag in bytes as enforced by Holochain
+pub const MAX_LINK_TAG_SIZE: usize = 1000;
+
+/// The bytes every version 0 link tag starts with: the msgpack header of a
+/// map with the five fields of `LinkTagContentV0` and the name of its first
+/// field. The user tag only comes after them, so links with a version 0 tag
+/// can't be filtered by the prefix of their user tag, only be fetched all
+/// at once with this prefix.
+pub const LINK_TAG_V0_PREFIX: &[u8] = &[0x85, 0xa3, b't', b'a', b'g'];
+
+/// Versions of the link tag layout that pass validation. A decoder for each
+/// of them has to be kept in `VersionedLinkTag`, and a version must never be
+/// removed from this list in a running network since links that have
+/// already been written with it would then fail validation on new authorities.
+pub const ACCEPTED_LINK_TAG_VERSIONS: &[u8] = &[0, 1];
+
+/// Bytes at the end of a version 1 link tag: metadata length
+/// (u16, big endian), flags and version
+const LINK_TAG_TRAILER_LEN: usize = 4;
+
+/// Set in the flags byte if the link has a user tag (which may be empty)
+const LINK_TAG_FLAG_HAS_TAG: u8 = 0b0000_0001;
+/// Set in the flags byte if the respective optional metadata field is present
+const LINK_TAG_FLAG_HAS_BACKLINK: u8 = 0b0000_0010;
+const LINK_TAG_FLAG_HAS_CREATED_AT: u8 = 0b0000_0100;
+const LINK_TAG_FLAG_HAS_CREATED_BY: u8 = 0b0000_1000;
+const LINK_TAG_FLAG_HAS_POSITION: u8 = 0b0001_0000;
+const LINK_TAG_FLAG_HAS_EXPIRES_AT: u8 = 0b0010_0000;
+const LINK_TAG_FLAG_HAS_SOURCE_ANCHOR: u8 = 0b0100_0000;
+
+/// Digits of fractional index keys, in ascending order
+pub const POSITION_DIGITS: &[u8] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// Maximum length of a fractional index key
+pub const MAX_POSITION_LEN: usize = 255;
+
+const HASH_LEN: usize = 39;
+const TIMESTAMP_LEN: usize = 8;
+
+const NODE_TYPE_AGENT: u8 = 0;
+const NODE_TYPE_ANCHOR: u8 = 1;
+const NODE_TYPE_THING: u8 = 2;
+
+/// The LinkTagContent as it was msgpack encoded before link tags were
+/// versioned. Must not be changed.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LinkTagContentV0 {
+    pub tag: Option<Vec<u8>>,
+    pub backlink_action_hash: Option<ActionHash>,
+    pub target_node_id: NodeId,
+    pub thing_created_at: Option<Timestamp>,
+    pub thing_created_by: Option<AgentPubKey>,
+}
+
+/// The metadata of a version 1 link tag, i.e. everything except for the
+/// user tag. It is written as raw bytes in the following order, with
+/// optional fields only present if their flag is set:
+///
+/// `[backlink action hash: 39][thing created at: i64][thing created by: 39][position length: u8][position][expires at: i64][source anchor length: u16][source anchor][target node type: u8][target node id]`
+///
+/// The target node id is a raw 39 byte hash for Agents and Things and the
+/// UTF-8 encoded anchor string for Anchors. New optional fields can be added
+/// with a new flag without changing the version.
+#[derive(Clone, Debug)]
+pub struct CompactLinkTagMeta {
+    pub backlink_action_hash: Option<ActionHash>,
+    pub target_node_id: NodeId,
+    pub thing_created_at: Option<Timestamp>,
+    pub thing_created_by: Option<AgentPubKey>,
+    pub position: Option<String>,
+    pub expires_at: Option<Timestamp>,
+    pub source_anchor: Option<String>,
+}
+
+impl CompactLinkTagMeta {
+    fn flags(&self) -> u8 {
+        let mut flags = 0;
+        if self.backlink_action_hash.is_some() {
+            flags |= LINK_TAG_FLAG_HAS_BACKLINK;
+        }
+        if self.thing_created_at.is_some() {
+            flags |= LINK_TAG_FLAG_HAS_CREATED_AT;
+        }
+        if self.thing_created_by.is_some() {
+            flags |= LINK_TAG_FLAG_HAS_CREATED_BY;
+        }
+        if self.position.is_some() {
+            flags |= LINK_TAG_FLAG_HAS_POSITION;
+        }
+        if self.expires_at.is_some() {
+            flags |= LINK_TAG_FLAG_HAS_EXPIRES_AT;
+        }
+        if self.source_anchor.is_some() {
+            flags |= LINK_TAG_FLAG_HAS_SOURCE_ANCHOR;
+        }
+        flags
+    }
+
+    fn encode(&self) -> ExternResult<Vec<u8>> {
+        let mut bytes = Vec::new();
+        if let Some(backlink_action_hash) = &self.backlink_action_hash {
+            bytes.extend_from_slice(backlink_action_hash.get_raw_39());
+        }
+        if let Some(thing_created_at) = &self.thing_created_at {
+            bytes.extend(thing_created_at.as_micros().to_be_bytes());
+        }
+        if let Some(thing_created_by) = &self.thing_created_by {
+            bytes.extend_from_slice(thing_created_by.get_raw_39());
+        }
+        if let Some(position) = &self.position {
+            let position_len = u8::try_from(position.len()).map_err(|_| {
+                wasm_error!(WasmErrorInner::Guest(format!(
+                    "Position may be at most {MAX_POSITION_LEN} bytes long"
+                )))
+            })?;
+            bytes.push(position_len);
+            bytes.extend_from_slice(position.as_bytes());
+        }
+        if let Some(expires_at) = &self.expires_at {
+            bytes.extend(expires_at.as_micros().to_be_bytes());
+        }
+        if let Some(source_anchor) = &self.source_anchor {
+            let source_anchor_len = u16::try_from(source_anchor.len()).map_err(|_| {
+                wasm_error!(WasmErrorInner::Guest(
+                    "Failed to encode link tag content: source anchor too large".into()
+                ))
+            })?;
+            bytes.extend(source_anchor_len.to_be_bytes());
+            bytes.extend_from_slice(source_anchor.as_bytes());
+        }
+        match &self.target_node_id {
+            NodeId::Agent(agent) => {
+                bytes.push(NODE_TYPE_AGENT);
+                bytes.extend_from_slice(agent.get_raw_39());
+            }
+            NodeId::Anchor(anchor) => {
+                bytes.push(NODE_TYPE_ANCHOR);
+                bytes.extend_from_slice(anchor.as_bytes());
+            }
+            NodeId::Thing(thing_id) => {
+                bytes.push(NODE_TYPE_THING);
+                bytes.extend_from_slice(thing_id.get_raw_39());
+            }
+        }
+        Ok(bytes)
+    }
+
+    fn decode(mut bytes: &[u8], flags: u8) -> Option<Self> {
+        let backlink_action_hash = match flags & LINK_TAG_FLAG_HAS_BACKLINK {
+            0 => None,
+            _ => {
+                Some(ActionHash::try_from_raw_39(take_bytes(&mut bytes, HASH_LEN)?.to_vec()).ok()?)
+            }
+        };
+        let thing_created_at = match flags & LINK_TAG_FLAG_HAS_CREATED_AT {
+            0 => None,
+            _ => Some(Timestamp::from_micros(i64::from_be_bytes(
+                take_bytes(&mut bytes, TIMESTAMP_LEN)?.try_into().ok()?,
+            ))),
+        };
+        let thing_created_by = match flags & LINK_TAG_FLAG_HAS_CREATED_BY {
+            0 => None,
+            _ => Some(
+                AgentPubKey::try_from_raw_39(take_bytes(&mut bytes, HASH_LEN)?.to_vec()).ok()?,
+            ),
+        };
+        let position = match flags & LINK_TAG_FLAG_HAS_POSITION {
+            0 => None,
+            _ => {
+                let position_len = take_bytes(&mut bytes, 1)?[0] as usize;
+                let position = take_bytes(&mut bytes, position_len)?;
+                Some(String::from_utf8(position.to_vec()).ok()?)
+            }
+        };
+        let expires_at = match flags & LINK_TAG_FLAG_HAS_EXPIRES_AT {
+            0 => None,
+            _ => Some(Timestamp::from_micros(i64::from_be_bytes(
+                take_bytes(&mut bytes, TIMESTAMP_LEN)?.try_into().ok()?,
+            ))),
+        };
+        let source_anchor = match flags & LINK_TAG_FLAG_HAS_SOURCE_ANCHOR {
+            0 => None,
+            _ => {
+                let source_anchor_len =
+                    u16::from_be_bytes(take_bytes(&mut bytes, 2)?.try_into().ok()?) as usize;
+                let source_anchor = take_bytes(&mut bytes, source_anchor_len)?;
+                Some(String::from_utf8(source_anchor.to_vec()).ok()?)
+            }
+        };
+        let target_node_type = take_bytes(&mut bytes, 1)?[0];
+        let target_node_id = match target_node_type {
+            NODE_TYPE_AGENT if bytes.len() == HASH_LEN => {
+                NodeId::Agent(AgentPubKey::try_from_raw_39(bytes.to_vec()).ok()?)
+            }
+            NODE_TYPE_ANCHOR => NodeId::Anchor(String::from_utf8(bytes.to_vec()).ok()?),
+            NODE_TYPE_THING if bytes.len() == HASH_LEN => {
+                NodeId::Thing(ActionHash::try_from_raw_39(bytes.to_vec()).ok()?)
+            }
+            _ => return None,
+        };
+        Some(CompactLinkTagMeta {
+            backlink_action_hash,
+            target_node_id,
+            thing_created_at,
+            thing_created_by,
+            position,
+            expires_at,
+            source_anchor,
+        })
+    }
+}
+
+/// Splits off the first `len` bytes
+fn take_bytes<'a>(bytes: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+    if bytes.len() < len {
+        return None;
+    }
+    let (taken, rest) = bytes.split_at(len);
+    *bytes = rest;
+    Some(taken)
+}
+
+/// The parts of a version 1 link tag
+struct LinkTagParts<'a> {
+    tag: Option<Vec<u8>>,
+    meta: &'a [u8],
+    flags: u8,
+}
+
+/// Splits a version 1 link tag into its user tag, metadata and flags
+fn split_link_tag(tag: &[u8]) -> Option<LinkTagParts<'_>> {
+    let trailer_start = tag.len().checked_sub(LINK_TAG_TRAILER_LEN)?;
+    let trailer = &tag[trailer_start..];
+    let meta_len = u16::from_be_bytes([trailer[0], trailer[1]]) as usize;
+    let meta_start = trailer_start.checked_sub(meta_len)?;
+    let flags = trailer[2];
+    let user_tag = match flags & LINK_TAG_FLAG_HAS_TAG {
+        0 if meta_start == 0 => None,
+        0 => return None,
+        _ => Some(tag[..meta_start].to_vec()),
+    };
+    Some(LinkTagParts {
+        tag: user_tag,
+        meta: &tag[meta_start..trailer_start],
+        flags,
+    })
+}
+
+/// Joins the user tag, metadata and trailer of a version 1 link tag
+fn join_link_tag(
+    tag: Option<Vec<u8>>,
+    meta: Vec<u8>,
+    mut flags: u8,
+    version: u8,
+) -> ExternResult<Vec<u8>> {
+    let meta_len = u16::try_from(meta.len()).map_err(|_| {
+        wasm_error!(WasmErrorInner::Guest(
+            "Failed to encode link tag content: metadata too large".into()
+        ))
+    })?;
+    if tag.is_some() {
+        flags |= LINK_TAG_FLAG_HAS_TAG;
+    }
+    let mut bytes = tag.unwrap_or_default();
+    bytes.extend(meta);
+    bytes.extend(meta_len.to_be_bytes());
+    bytes.push(flags);
+    bytes.push(version);
+    Ok(bytes)
+}
+
+/// A link tag as it is stored on the DHT, in one of the accepted versions
+/// of the layout
+#[derive(Clone, Debug)]
+pub enum VersionedLinkTag {
+    /// The whole LinkTagContent msgpack encoded
+    V0(LinkTagContentV0),
+    /// `[user tag][metadata][metadata length: u16][flags: u8][version: u8]`
+    /// with the compact binary encoding of `CompactLinkTagMeta` for the
+    /// metadata.
+    ///
+    /// The user tag comes first so that links can be filtered by the prefix
+    /// of their user tag with the `tag_prefix` of get_links.
+    V1 {
+        tag: Option<Vec<u8>>,
+        meta: CompactLinkTagMeta,
+    },
+}
+
+impl VersionedLinkTag {
+    pub fn version(&self) -> u8 {
+        match self {
+            VersionedLinkTag::V0(_) => 0,
+            VersionedLinkTag::V1 { .. } => 1,
+        }
+    }
+
+    pub fn decode(tag: &[u8]) -> ExternResult<Self> {
+        // Versioned layouts carry their version in the last byte. Tags of
+        // version 0 have no version byte so we fall back to decoding them
+        // if the versioned decoder doesn't succeed.
+        let decoded = match tag.last() {
+            Some(1) => Self::decode_v1(tag),
+            _ => None,
+        };
+        match decoded {
+            Some(versioned_link_tag) => Ok(versioned_link_tag),
+            None => Self::decode_v0(tag),
+        }
+    }
+
+    pub fn encode(self) -> ExternResult<Vec<u8>> {
+        match self {
+            VersionedLinkTag::V0(content) => Ok(ExternIO::encode(content)
+                .map_err(|e| {
+                    wasm_error!(WasmErrorInner::Guest(format!(
+                        "Failed to encode link tag content: {e}"
+                    )))
+                })?
+                .into_vec()),
+            VersionedLinkTag::V1 { tag, meta } => {
+                join_link_tag(tag, meta.encode()?, meta.flags(), 1)
+            }
+        }
+    }
+
+    fn decode_v0(tag: &[u8]) -> ExternResult<Self> {
+        let content = ExternIO::from(tag.to_vec())
+            .decode::<LinkTagContentV0>()
+            .map_err(|e| {
+                wasm_error!(WasmErrorInner::Guest(format!(
+                    "Failed to decode link tag content: {e}"
+                )))
+            })?;
+        Ok(VersionedLinkTag::V0(content))
+    }
+
+    fn decode_v1(tag: &[u8]) -> Option<Self> {
+        let parts = split_link_tag(tag)?;
+        let meta = CompactLinkTagMeta::decode(parts.meta, parts.flags)?;
+        Some(VersionedLinkTag::V1 {
+            tag: parts.tag,
+            meta,
+        })
+    }
+}
+
+impl From<VersionedLinkTag> for LinkTagContent {
+    fn from(versioned_link_tag: VersionedLinkTag) -> Self {
+        match versioned_link_tag {
+            VersionedLinkTag::V0(content) => LinkTagContent {
+                tag: content.tag,
+                backlink_action_hash: content.backlink_action_hash,
+                target_node_id: content.target_node_id,
+                thing_created_at: content.thing_created_at,
+                thing_created_by: content.thing_created_by,
+                position: None,
+                expires_at: None,
+                source_anchor: None,
+            },
+            VersionedLinkTag::V1 { tag, meta } => LinkTagContent {
+                tag,
+                backlink_action_hash: meta.backlink_action_hash,
+                target_node_id: meta.target_node_id,
+                thing_created_at: meta.thing_created_at,
+                thing_created_by: meta.thing_created_by,
+                position: meta.position,
+                expires_at: meta.expires_at,
+                source_anchor: meta.source_anchor,
+            },
+        }
+    }
+}
+
+/// Serializes the link tag content in the current version of the layout.
+/// Fails with `LinkTagError::TagTooLarge` if the user tag doesn't fit into
+/// the link tag next to the metadata.
 pub fn serialize_link_tag(link_tag_content: LinkTagContent) -> ExternResult<Vec<u8>> {
-    Ok(ExternIO::encode(link_tag_content)
-        .map_err(|e| {
-            wasm_error!(WasmErrorInner::Guest(format!(
-                "Failed to encode link tag content: {e}"
-            )))
-        })?
-        .into_vec())
+    let meta = CompactLinkTagMeta {
+        backlink_action_hash: link_tag_content.backlink_action_hash,
+        target_node_id: link_tag_content.target_node_id,
+        thing_created_at: link_tag_content.thing_created_at,
+        thing_created_by: link_tag_content.thing_created_by,
+        position: link_tag_content.position,
+        expires_at: link_tag_content.expires_at,
+        source_anchor: link_tag_content.source_anchor,
+    };
+    if let Some(tag) = &link_tag_content.tag {
+        let max = max_user_tag_size_for_meta(&meta)?;
+        if tag.len() > max {
+            return Err(LinkTagError::TagTooLarge {
+                max,
+                actual: tag.len(),
+            }
+            .into());
+        }
+    }
+    VersionedLinkTag::V1 {
+        tag: link_tag_content.tag,
+        meta,
+    }
+    .encode()
 }
 
-pub fn deserialize_link_tag(tag: Vec<u8>) -> ExternResult<LinkTagContent> {
-    ExternIO::from(tag).decode::<LinkTagContent>().map_err(|e| {
-        wasm_error!(WasmErrorInner::Guest(format!(
-            "Failed to decode link tag content: {e}"
-        )))
+/// The number of bytes left for the user tag once the given metadata
+/// has been encoded
+fn max_user_tag_size_for_meta(meta: &CompactLinkTagMeta) -> ExternResult<usize> {
+    Ok(MAX_LINK_TAG_SIZE.saturating_sub(meta.encode()?.len() + LINK_TAG_TRAILER_LEN))
+}
+
+/// The maximum size of a user tag on a link pointing to the given node,
+/// assuming that all optional metadata (backlink and Thing creation info)
+/// is present. Links that are part of an ordered collection additionally
+/// need one byte more than the length of their position, expiring links
+/// 8 bytes more and links from anchors 2 bytes more than the length of the
+/// anchor string.
+pub fn max_user_tag_size(target_node_id: NodeId) -> ExternResult<usize> {
+    max_user_tag_size_for_meta(&CompactLinkTagMeta {
+        backlink_action_hash: Some(ActionHash::from_raw_36(vec![0; 36])),
+        target_node_id,
+        thing_created_at: Some(Timestamp::from_micros(0)),
+        thing_created_by: Some(AgentPubKey::from_raw_36(vec![0; 36])),
+        position: None,
+        expires_at: None,
+        source_anchor: None,
     })
 }
+
+/// Whether the given string is a valid fractional index key, i.e. a non-empty
+/// string of `POSITION_DIGITS` that doesn't end with the smallest digit.
+/// Not ending with the smallest digit guarantees that there is always
+/// another key that sorts before it.
+pub fn is_valid_position(position: &str) -> bool {
+    !position.is_empty()
+        && position.len() <= MAX_POSITION_LEN
+        && position.bytes().all(|b| POSITION_DIGITS.contains(&b))
+        && !position.ends_with(POSITION_DIGITS[0] as char)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkTagError {
+    /// The user tag doesn't fit into the link tag next to the metadata
+    TagTooLarge { max: usize, actual: usize },
+}
+
+impl From<LinkTagError> for WasmError {
+    fn from(e: LinkTagError) -> Self {
+        match e {
+            LinkTagError::TagTooLarge { max, actual } => wasm_error!(WasmErrorInner::Guest(
+                format!("TagTooLarge {{ max: {max}, actual: {actual} }}: the link tag may be at most {max} bytes long for this link but is {actual} bytes long")
+            )),
+        }
+    }
+}
+
+/// Deserializes a link tag written in any of the known versions of the layout
+pub fn deserialize_link_tag(tag: Vec<u8>) -> ExternResult<LinkTagContent> {
+    Ok(VersionedLinkTag::decode(&tag)?.into())
+}
+
+/// Whether the user tag of a link starts with the given prefix. Used to
+/// re-check links returned by a get_links call with a `tag_prefix` since
+/// that prefix may also match metadata bytes of links with a shorter tag.
+pub fn link_tag_has_prefix(link_tag_content: &LinkTagContent, prefix: &[u8]) -> bool {
+    match &link_tag_content.tag {
+        Some(tag) => tag.starts_with(prefix),
+        None => prefix.is_empty(),
+    }
+}
+
+/// Checks that a link tag of a link between nodes decodes in one of the
+/// accepted versions of the layout
+pub fn validate_link_tag(tag: &LinkTag) -> ExternResult<ValidateCallbackResult> {
+    match VersionedLinkTag::decode(&tag.0) {
+        Ok(versioned_link_tag) => {
+            if ACCEPTED_LINK_TAG_VERSIONS.contains(&versioned_link_tag.version()) {
+                let link_tag_content = LinkTagContent::from(versioned_link_tag);
+                match link_tag_content.position {
+                    Some(position) if !is_valid_position(&position) => {
+                        Ok(ValidateCallbackResult::Invalid(format!(
+                            "Link tag contains an invalid position: {position}"
+                        )))
+                    }
+                    _ => Ok(ValidateCallbackResult::Valid),
+                }
+            } else {
+                Ok(ValidateCallbackResult::Invalid(format!(
+                    "Link tag version {} is not accepted",
+                    versioned_link_tag.version()
+                )))
+            }
+        }
+        Err(e) => Ok(ValidateCallbackResult::Invalid(format!(
+            "Link tag could not be decoded: {e:?}"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action_hash(byte: u8) -> ActionHash {
+        ActionHash::from_raw_36(vec![byte; 36])
+    }
+
+    fn agent(byte: u8) -> AgentPubKey {
+        AgentPubKey::from_raw_36(vec![byte; 36])
+    }
+
+    fn compact_meta() -> CompactLinkTagMeta {
+        CompactLinkTagMeta {
+            backlink_action_hash: Some(action_hash(1)),
+            target_node_id: NodeId::Thing(action_hash(2)),
+            thing_created_at: Some(Timestamp::from_micros(1_700_000_000_000_000)),
+            thing_created_by: Some(agent(3)),
+            position: None,
+            expires_at: None,
+            source_anchor: None,
+        }
+    }
+
+    fn assert_meta_eq(content: &LinkTagContent, meta: &CompactLinkTagMeta) {
+        assert_eq!(content.backlink_action_hash, meta.backlink_action_hash);
+        assert_eq!(content.target_node_id, meta.target_node_id);
+        assert_eq!(content.thing_created_at, meta.thing_created_at);
+        assert_eq!(content.thing_created_by, meta.thing_created_by);
+        assert_eq!(content.position, meta.position);
+        assert_eq!(content.expires_at, meta.expires_at);
+        assert_eq!(content.source_anchor, meta.source_anchor);
+    }
+
+    /// Encodes the link tag, decodes it again and checks the version
+    fn round_trip(versioned_link_tag: VersionedLinkTag) -> LinkTagContent {
+        let version = versioned_link_tag.version();
+        let decoded = VersionedLinkTag::decode(&versioned_link_tag.encode().unwrap()).unwrap();
+        assert_eq!(decoded.version(), version);
+        decoded.into()
+    }
+
+    #[test]
+    fn decodes_version_0() {
+        let content = LinkTagContentV0 {
+            tag: Some(b"tag".to_vec()),
+            backlink_action_hash: Some(action_hash(1)),
+            target_node_id: NodeId::Anchor("ANCHOR".into()),
+            thing_created_at: None,
+            thing_created_by: Some(agent(3)),
+        };
+        let tag = ExternIO::encode(content).unwrap().into_vec();
+        assert!(tag.starts_with(LINK_TAG_V0_PREFIX));
+
+        let decoded = VersionedLinkTag::decode(&tag).unwrap();
+        assert_eq!(decoded.version(), 0);
+        let decoded = LinkTagContent::from(decoded);
+        assert_eq!(decoded.tag, Some(b"tag".to_vec()));
+        assert_eq!(decoded.backlink_action_hash, Some(action_hash(1)));
+        assert_eq!(decoded.target_node_id, NodeId::Anchor("ANCHOR".into()));
+        assert_eq!(decoded.thing_created_at, None);
+        assert_eq!(decoded.thing_created_by, Some(agent(3)));
+        assert_eq!(decoded.position, None);
+        assert_eq!(decoded.expires_at, None);
+    }
+
+    #[test]
+    fn round_trips_version_1() {
+        let with_position = CompactLinkTagMeta {
+            position: Some("a0V".into()),
+            ..compact_meta()
+        };
+        let with_expiry = CompactLinkTagMeta {
+            expires_at: Some(Timestamp::from_micros(1_800_000_000_000_000)),
+            ..with_position.clone()
+        };
+        let from_anchor = CompactLinkTagMeta {
+            target_node_id: NodeId::Anchor("TARGET".into()),
+            source_anchor: Some("SOURCE".into()),
+            ..with_expiry.clone()
+        };
+        let minimal = CompactLinkTagMeta {
+            backlink_action_hash: None,
+            target_node_id: NodeId::Agent(agent(4)),
+            thing_created_at: None,
+            thing_created_by: None,
+            position: None,
+            expires_at: None,
+            source_anchor: None,
+        };
+        for meta in [
+            compact_meta(),
+            with_position,
+            with_expiry,
+            from_anchor,
+            minimal,
+        ] {
+            for tag in [None, Some(Vec::new()), Some(b"tag".to_vec())] {
+                let decoded = round_trip(VersionedLinkTag::V1 {
+                    tag: tag.clone(),
+                    meta: meta.clone(),
+                });
+                assert_eq!(decoded.tag, tag);
+                assert_meta_eq(&decoded, &meta);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_flags_without_their_field() {
+        let mut tag = VersionedLinkTag::V1 {
+            tag: None,
+            meta: compact_meta(),
+        }
+        .encode()
+        .unwrap();
+        let flags = tag.len() - 2;
+        tag[flags] |= LINK_TAG_FLAG_HAS_EXPIRES_AT;
+
+        assert!(matches!(
+            validate_link_tag(&LinkTag::new(tag)).unwrap(),
+            ValidateCallbackResult::Invalid(_)
+        ));
+    }
+
+    #[test]
+    fn rejects_unknown_versions() {
+        let mut tag = VersionedLinkTag::V1 {
+            tag: Some(b"tag".to_vec()),
+            meta: compact_meta(),
+        }
+        .encode()
+        .unwrap();
+        *tag.last_mut().unwrap() = LINK_TAG_VERSION + 1;
+
+        assert!(VersionedLinkTag::decode(&tag).is_err());
+        assert!(matches!(
+            validate_link_tag(&LinkTag::new(tag)).unwrap(),
+            ValidateCallbackResult::Invalid(_)
+        ));
+    }
+
+    #[test]
+    fn rejects_oversized_user_tags() {
+        let content = |tag: Vec<u8>| LinkTagContent {
+            tag: Some(tag),
+            backlink_action_hash: None,
+            target_node_id: NodeId::Anchor("A".into()),
+            thing_created_at: None,
+            thing_created_by: None,
+            position: None,
+            expires_at: None,
+            source_anchor: None,
+        };
+        // The metadata is the target node type and the anchor string
+        let max = MAX_LINK_TAG_SIZE - 2 - LINK_TAG_TRAILER_LEN;
+
+        let tag = serialize_link_tag(content(vec![0; max])).unwrap();
+        assert_eq!(tag.len(), MAX_LINK_TAG_SIZE);
+
+        let err = serialize_link_tag(content(vec![0; max + 1])).unwrap_err();
+        assert_eq!(
+            err,
+            LinkTagError::TagTooLarge {
+                max,
+                actual: max + 1
+            }
+            .into()
+        );
+    }
+}
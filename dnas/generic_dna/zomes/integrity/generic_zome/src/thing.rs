@@ -4,6 +4,10 @@ use hdi::prelude::*;
 #[hdk_entry_helper]
 pub struct ThingEntry {
     pub content: String,
+    /// If set, the Thing is treated as absent from this time on and gets
+    /// deleted by the scheduled cleanup of its author
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<Timestamp>,
 }
 
 pub fn validate_create_thing(
@@ -4,8 +4,12 @@ pub fn validate_create_link_to_thing(
     _action: CreateLink,
     _base_address: AnyLinkableHash,
     target_address: AnyLinkableHash,
-    _tag: LinkTag,
+    tag: LinkTag,
 ) -> ExternResult<ValidateCallbackResult> {
+    let tag_result = crate::validate_link_tag(&tag)?;
+    if let ValidateCallbackResult::Invalid(_) = tag_result {
+        return Ok(tag_result);
+    }
     // Check the entry type for the given action hash
     let action_hash =
         target_address
@@ -12,23 +12,61 @@ import {
   RoleNameCallZomeRequest,
 } from "@holochain/client";
 import {
+  BatchGetNodeAndLinkedNodeIdsInput,
+  CountLinksInput,
   CreateOrDeleteLinksInput,
   CreateThingInput,
   DeleteThingInput,
+  DeletedExpired,
+  ExpandInput,
+  FindMyOrphansInput,
+  FindPathInput,
   GenericZomeSignal,
+  GetLinkedNodesInput,
+  GetLinkedNodesPageInput,
+  GetLinkHistoryInput,
+  GetNodeAndLinkedNodeIdsInput,
+  GetNodeChangesSinceInput,
+  GetRecordsInput,
+  GetStrategy,
+  GetThingInput,
+  GetThingsInput,
+  GraphIssue,
+  GraphPath,
+  HasLinkInput,
+  InsertItemInput,
   LinkDirection,
   LinkDirectionRust,
   LinkInput,
+  LinkCounts,
+  LinkCursor,
+  LinkHistoryEntry,
+  LinkedNodesFilter,
   LinkInputRust,
+  MoveItemInput,
   NodeAndLinkedIds,
+  NodeChangesSince,
   NodeContent,
   NodeId,
   NodeIdAndMetaTag,
   NodeIdAndTag,
-  RemoteSignalInput,
+  NodeLinkMeta,
+  NodeLinkType,
+  OrphanAction,
+  Page,
+  QueryGraphResult,
+  RepairGraphInput,
+  SortBy,
+  SortOrder,
+  Subgraph,
   Tag,
   Thing,
+  ThingDetails,
   ThingId,
+  TrashInput,
+  TrashThingInput,
+  TraverseFilters,
+  TraverseInput,
   UpdateThingInput,
 } from "./types.js";
 
@@ -190,8 +228,6 @@ export class SimpleHolochain {
   private agentStores: Record<AgentPubKeyB64, NodeStore> = {};
   private thingStores: Record<ActionHashB64, NodeStore> = {};
 
-  private allAgents: AgentPubKey[] = [];
-
   private constructor(
     client: AppClient,
     zomeClient: ZomeClient<GenericZomeSignal>,
@@ -203,22 +239,6 @@ export class SimpleHolochain {
     this.roleName = roleName;
     this.zomeName = zomeName;
 
-    const allAgentsNodeStore = this.nodeStore({
-      type: "Anchor",
-      id: "SIMPLE_HOLOCHAIN_ALL_AGENTS",
-    });
-    allAgentsNodeStore.subscribe((val) => {
-      if (val.status === "complete" && val.value.content.type === "Anchor") {
-        this.allAgents = val.value.linkedNodeIds
-          .filter((nodeIdAndTag) => nodeIdAndTag.node_id.type === "Agent")
-          .map((nodeIdAndTag) => nodeIdAndTag.node_id.id) as AgentPubKey[];
-        console.log(
-          "GOT AGENTS: ",
-          this.allAgents.map((a) => encodeHashToBase64(a))
-        );
-      }
-    });
-
     // TODO set up signal listener. Potentially emit signal to conductor
     this.zomeClient.onSignal(async (s) => {
       if (s.type === "Remote") {
@@ -343,17 +363,8 @@ export class SimpleHolochain {
           break;
         }
       }
-      // If it's a local signal, forward it to all other agents
-      if (s.type === "Local") {
-        const input: RemoteSignalInput = {
-          signal: {
-            type: "Remote",
-            content: signal,
-          },
-          agents: this.allAgents,
-        };
-        await this.callZome("remote_signal", input);
-      }
+      // Local signals are forwarded to the interested agents by the zome
+      // itself in post_commit
     });
     window.__SIMPLE_PEER_POLL_INTERVAL__ = window.setInterval(
       () => this.pollStores(),
@@ -616,9 +627,11 @@ export class SimpleHolochain {
   }
 
   async getRecords(
-    hashes: AnyDhtHash[]
+    hashes: AnyDhtHash[],
+    getStrategy?: GetStrategy
   ): Promise<(HolochainRecord | undefined)[]> {
-    return this.callZome("get_records", hashes);
+    const input: GetRecordsInput = { hashes, get_strategy: getStrategy };
+    return this.callZome("get_records", input);
   }
 
   /**
@@ -628,14 +641,20 @@ export class SimpleHolochain {
    *
    * @param content
    * @param links
+   * @param expiresAt time (in microseconds) from which on the Thing is treated as absent
    * @returns
    */
-  async createThing(content: string, links?: LinkInput[]): Promise<Thing> {
+  async createThing(
+    content: string,
+    links?: LinkInput[],
+    expiresAt?: number
+  ): Promise<Thing> {
     let input: CreateThingInput = {
       content,
       links: links
         ? links.map((link) => linkInputToRustFormat(link))
         : undefined,
+      expires_at: expiresAt,
     };
     return this.callZome("create_thing", input);
   }
@@ -657,12 +676,14 @@ export class SimpleHolochain {
   }
 
   /**
-   * Deletes a Thing as well as optionally any backlinks of
-   * 'bidirectional' links that were created with this Thing
-   * as the source. A Thing is unaware of 'from' links
-   * pointing to it from elsewhere (including bidirectional links * that were created from another node as the src).
-   * Such links (or any other links) need to be explicitly deleted * by passing them with the 'links' argument or using the
-   * `deleteLink` function.
+   * Deletes a Thing. With deleteBacklinks, the backlinks of 'bidirectional'
+   * links that were created with this Thing as the source are deleted as
+   * well as the links pointing to it that can be found: bidirectional links
+   * through their backlink stored at the Thing, 'from' links stored at the
+   * Thing and the links to it in the calling agent's source chain. Links
+   * pointing to it that other agents created without a backlink need to be
+   * explicitly deleted by passing them with the 'links' argument or using
+   * the `deleteLink` function.
    *
    * @param thingId
    * @param deleteBacklinks
@@ -687,91 +708,616 @@ export class SimpleHolochain {
     return this.callZome("delete_thing", input);
   }
 
+  /**
+   * Moves a thing to the trash. Trashed things are left out by the reads
+   * until they are restored with `restoreThing`.
+   *
+   * @param thingId
+   * @param global whether to use the global trash instead of the own one
+   * @returns
+   */
+  async trashThing(thingId: ThingId, global = false): Promise<NodeLinkMeta> {
+    const input: TrashThingInput = { thing_id: thingId, global };
+    return this.callZome("trash_thing", input);
+  }
+
+  /**
+   * Takes a thing out of the global trash and the own trash. The trashes of
+   * other agents are left untouched.
+   *
+   * @param thingId
+   * @returns
+   */
+  async restoreThing(thingId: ThingId): Promise<void> {
+    return this.callZome("restore_thing", thingId);
+  }
+
+  /**
+   * Gets the things in the trash
+   *
+   * @param global
+   * @returns
+   */
+  async getTrash(global = false): Promise<Thing[]> {
+    const input: TrashInput = { global };
+    return this.callZome("get_trash", input);
+  }
+
+  /**
+   * Deletes the things in the trash for good. For the global trash only
+   * the things moved there by the calling agent are deleted.
+   *
+   * @param global
+   * @returns The ids of the deleted things
+   */
+  async emptyTrash(global = false): Promise<ThingId[]> {
+    const input: TrashInput = { global };
+    return this.callZome("empty_trash", input);
+  }
+
+  /**
+   * Deletes all expired Things and links authored by the current agent.
+   * Reads already leave out expired items, and if the zome is built with
+   * the scheduled-cleanup feature this also runs periodically.
+   *
+   * @returns the ids of the deleted Things and links
+   */
+  async deleteExpired(): Promise<DeletedExpired> {
+    return this.callZome("delete_expired", null);
+  }
+
+  /**
+   * Finds the Things created by the current agent that no live link points
+   * to, and optionally re-links them under an anchor or deletes them
+   *
+   * @param action
+   * @param getStrategy
+   * @returns the orphaned Things
+   */
+  async findMyOrphans(
+    action?: OrphanAction,
+    getStrategy?: GetStrategy
+  ): Promise<Thing[]> {
+    const input: FindMyOrphansInput = {
+      action,
+      get_strategy: getStrategy,
+    };
+    return this.callZome("find_my_orphans", input);
+  }
+
   /**
    * Gets the latest known version of a thing (it's possible that other peers
    * have updated it but they are now offline and we don't know about it)
    *
    * @param thingId
+   * @param getStrategy "Local" to only look into the local databases
+   * @param includeTrashed whether to return the thing if it is in the trash
    * @returns
    */
-  async getThing(thingId: ThingId): Promise<Thing | undefined> {
-    return this.callZome("get_latest_thing", thingId);
+  async getThing(
+    thingId: ThingId,
+    getStrategy?: GetStrategy,
+    includeTrashed = false
+  ): Promise<Thing | undefined> {
+    const input: GetThingInput = {
+      thing_id: thingId,
+      get_strategy: getStrategy,
+      include_trashed: includeTrashed,
+    };
+    return this.callZome("get_latest_thing", input);
   }
 
   /**
    * Gets the latest known version of a thing (it's possible that other peers
    * have updated it but they are now offline and we don't know about it)
    *
+   * @param thingIds
+   * @param getStrategy "Local" to only look into the local databases
+   * @param includeTrashed whether to return things that are in the trash
+   * @returns
+   */
+  async getThings(
+    thingIds: ThingId[],
+    getStrategy?: GetStrategy,
+    includeTrashed = false
+  ): Promise<(Thing | undefined)[]> {
+    const input: GetThingsInput = {
+      thing_ids: thingIds,
+      get_strategy: getStrategy,
+      include_trashed: includeTrashed,
+    };
+    return this.callZome("get_latest_things", input);
+  }
+
+  /**
+   * Gets the status of a thing (live, deleted or not found) together with
+   * all its versions
+   *
    * @param thingId
+   * @param getStrategy
    * @returns
    */
-  async getThings(thingIds: ThingId[]): Promise<(Thing | undefined)[]> {
-    return this.callZome("get_latest_things", thingIds);
+  async getThingDetails(
+    thingId: ThingId,
+    getStrategy?: GetStrategy
+  ): Promise<ThingDetails> {
+    const input: GetThingInput = {
+      thing_id: thingId,
+      get_strategy: getStrategy,
+    };
+    return this.callZome("get_thing_details", input);
   }
 
   /**
-   * Get all the node ids that are linked from the specified source node
+   * Get all the node ids that are linked from the specified source node,
+   * optionally only those whose links match the given filter
    *
    * @param src
+   * @param filter
+   * @param getStrategy
    * @returns
    */
-  async getAllLinkedNodeIds(src: NodeId): Promise<NodeIdAndMetaTag[]> {
-    return this.callZome("get_all_linked_node_ids", src);
+  async getAllLinkedNodeIds(
+    src: NodeId,
+    filter?: LinkedNodesFilter,
+    getStrategy?: GetStrategy
+  ): Promise<NodeIdAndMetaTag[]> {
+    const input: GetLinkedNodesInput = {
+      node_id: src,
+      ...filter,
+      get_strategy: getStrategy,
+    };
+    return this.callZome("get_all_linked_node_ids", input);
   }
 
   /**
-   * Get all the nodes that are linked from the specified source node
+   * Get all the nodes that are linked from the specified source node,
+   * optionally sorted
    *
    * @param src
+   * @param filter
+   * @param sort
+   * @param getStrategy
    * @returns
    */
-  async getAllLinkedNodes(src: NodeId): Promise<NodeContent[]> {
-    return this.callZome("get_all_linked_nodes", src);
+  async getAllLinkedNodes(
+    src: NodeId,
+    filter?: LinkedNodesFilter,
+    sort?: SortBy,
+    getStrategy?: GetStrategy
+  ): Promise<NodeContent[]> {
+    const input: GetLinkedNodesInput = {
+      node_id: src,
+      ...filter,
+      sort,
+      get_strategy: getStrategy,
+    };
+    return this.callZome("get_all_linked_nodes", input);
   }
 
   /**
    * Get all the agents that are linked from the specified source node
    *
    * @param src
+   * @param filter
+   * @param getStrategy
    * @returns
    */
   async getLinkedAgents(
-    src: NodeId
+    src: NodeId,
+    filter?: LinkedNodesFilter,
+    getStrategy?: GetStrategy
   ): Promise<[AgentPubKey, Tag | undefined][]> {
-    return this.callZome("get_linked_agents", src);
+    const input: GetLinkedNodesInput = {
+      node_id: src,
+      ...filter,
+      get_strategy: getStrategy,
+    };
+    return this.callZome("get_linked_agents", input);
   }
 
   /**
    * Get all the anchors that are linked from the specified source node
    *
    * @param src
+   * @param filter
+   * @param getStrategy
    * @returns
    */
-  async getLinkedAnchors(src: NodeId): Promise<[string, Tag | undefined][]> {
-    return this.callZome("get_linked_anchors", src);
+  async getLinkedAnchors(
+    src: NodeId,
+    filter?: LinkedNodesFilter,
+    getStrategy?: GetStrategy
+  ): Promise<[string, Tag | undefined][]> {
+    const input: GetLinkedNodesInput = {
+      node_id: src,
+      ...filter,
+      get_strategy: getStrategy,
+    };
+    return this.callZome("get_linked_anchors", input);
   }
 
   /**
    * Get the latest versions of all Things that are linked from the
-   * specified source node
+   * specified source node, optionally sorted
    *
    * @param src
+   * @param filter
+   * @param sort
+   * @param getStrategy
    * @returns
    */
-  async getLinkedThings(src: NodeId): Promise<[Thing, Tag | undefined][]> {
-    return this.callZome("get_linked_things", src);
+  async getLinkedThings(
+    src: NodeId,
+    filter?: LinkedNodesFilter,
+    sort?: SortBy,
+    getStrategy?: GetStrategy
+  ): Promise<[Thing, Tag | undefined][]> {
+    const input: GetLinkedNodesInput = {
+      node_id: src,
+      ...filter,
+      sort,
+      get_strategy: getStrategy,
+    };
+    return this.callZome("get_linked_things", input);
   }
 
   /**
-   * Gets the node content and linked node ids for for the given node id
+   * Get a page of the latest versions of the Things that are linked from the
+   * specified source node, ordered by link timestamp. Pass the returned
+   * next_cursor to get the next page. Trashed Things and Things that can't
+   * be found are skipped, so only the last page may have fewer than `limit`
+   * items.
+   *
+   * @param src
+   * @param limit
+   * @param cursor
+   * @param order
+   * @param filter
+   * @param getStrategy
+   * @returns
+   */
+  async getLinkedThingsPage(
+    src: NodeId,
+    limit: number,
+    cursor?: LinkCursor,
+    order: SortOrder = { type: "Ascending" },
+    filter?: LinkedNodesFilter,
+    getStrategy?: GetStrategy
+  ): Promise<Page<Thing>> {
+    const input: GetLinkedNodesPageInput = {
+      query: { node_id: src, ...filter, get_strategy: getStrategy },
+      limit,
+      cursor,
+      order,
+    };
+    return this.callZome("get_linked_things_page", input);
+  }
+
+  /**
+   * Get a page of the node ids that are linked from the specified source
+   * node, ordered by link timestamp
+   *
+   * @param src
+   * @param limit
+   * @param cursor
+   * @param order
+   * @param filter
+   * @param getStrategy
+   * @returns
+   */
+  async getAllLinkedNodeIdsPage(
+    src: NodeId,
+    limit: number,
+    cursor?: LinkCursor,
+    order: SortOrder = { type: "Ascending" },
+    filter?: LinkedNodesFilter,
+    getStrategy?: GetStrategy
+  ): Promise<Page<NodeIdAndMetaTag>> {
+    const input: GetLinkedNodesPageInput = {
+      query: { node_id: src, ...filter, get_strategy: getStrategy },
+      limit,
+      cursor,
+      order,
+    };
+    return this.callZome("get_all_linked_node_ids_page", input);
+  }
+
+  /**
+   * Get a page of the nodes that are linked from the specified source
+   * node, ordered by link timestamp
+   *
+   * @param src
+   * @param limit
+   * @param cursor
+   * @param order
+   * @param filter
+   * @param getStrategy
+   * @returns
+   */
+  async getAllLinkedNodesPage(
+    src: NodeId,
+    limit: number,
+    cursor?: LinkCursor,
+    order: SortOrder = { type: "Ascending" },
+    filter?: LinkedNodesFilter,
+    getStrategy?: GetStrategy
+  ): Promise<Page<NodeContent>> {
+    const input: GetLinkedNodesPageInput = {
+      query: { node_id: src, ...filter, get_strategy: getStrategy },
+      limit,
+      cursor,
+      order,
+    };
+    return this.callZome("get_all_linked_nodes_page", input);
+  }
+
+  /**
+   * Counts the links from a node per link type without fetching them. The
+   * counts are raw, i.e. they include expired links and links to things
+   * that are in the trash.
+   *
+   * @param src
+   * @param linkType
+   * @param tagPrefix
+   * @returns
+   */
+  async countLinks(
+    src: NodeId,
+    linkType?: NodeLinkType,
+    tagPrefix?: Tag
+  ): Promise<LinkCounts> {
+    const input: CountLinksInput = {
+      node_id: src,
+      link_type: linkType,
+      tag_prefix: tagPrefix,
+    };
+    return this.callZome("count_links", input);
+  }
+
+  /**
+   * Gets all links ever created from a node, including deleted ones together
+   * with who deleted them and when, ordered by creation time
+   *
+   * @param src
+   * @param linkType
+   * @param getStrategy
+   * @returns
+   */
+  async getLinkHistory(
+    src: NodeId,
+    linkType?: NodeLinkType,
+    getStrategy?: GetStrategy
+  ): Promise<LinkHistoryEntry[]> {
+    const input: GetLinkHistoryInput = {
+      node_id: src,
+      link_type: linkType,
+      get_strategy: getStrategy,
+    };
+    return this.callZome("get_link_history", input);
+  }
+
+  /**
+   * Checks the links from the given nodes for broken bidirectional pairs
+   * and links to deleted Things, and optionally deletes the broken links
+   * authored by the current agent
    *
    * @param nodeIds
+   * @param fix
+   * @param getStrategy
+   * @returns the issues found
+   */
+  async repairGraph(
+    nodeIds: NodeId[],
+    fix: boolean,
+    getStrategy?: GetStrategy
+  ): Promise<GraphIssue[]> {
+    const input: RepairGraphInput = {
+      node_ids: nodeIds,
+      fix,
+      get_strategy: getStrategy,
+    };
+    return this.callZome("repair_graph", input);
+  }
+
+  /**
+   * Counts the links for multiple nodes in a single zome call
+   *
+   * @param inputs
+   * @returns
+   */
+  async batchCountLinks(inputs: CountLinksInput[]): Promise<LinkCounts[]> {
+    return this.callZome("batch_count_links", inputs);
+  }
+
+  /**
+   * Checks whether a link with exactly the given tag exists from the
+   * source node to the destination node
+   *
+   * @param src
+   * @param dst
+   * @param tag
+   * @returns
+   */
+  async hasLink(src: NodeId, dst: NodeId, tag?: Tag): Promise<boolean> {
+    const input: HasLinkInput = { src, dst, tag };
+    return this.callZome("has_link", input);
+  }
+
+  /**
+   * Inserts an item into an ordered collection right after the reference
+   * item, or at the end if no reference item is given. Linked Things
+   * of an ordered collection are returned in their order by
+   * getLinkedThings. Fails if the item is already part of the collection,
+   * use moveItem to change its position instead.
+   *
+   * @param collection
+   * @param item
+   * @param reference
+   * @param tag
+   * @param direction Must be either To or Bidirectional
+   * @returns
+   */
+  async insertAfter(
+    collection: NodeId,
+    item: NodeId,
+    reference?: NodeId,
+    tag?: Tag,
+    direction: LinkDirection = LinkDirection.To
+  ): Promise<NodeLinkMeta> {
+    const input: InsertItemInput = {
+      collection,
+      item,
+      tag,
+      direction: linkDirectionToRustFormat(direction),
+      reference,
+    };
+    return this.callZome("insert_after", input);
+  }
+
+  /**
+   * Inserts an item into an ordered collection right before the reference
+   * item, or at the start if no reference item is given. Fails if the
+   * item is already part of the collection.
+   *
+   * @param collection
+   * @param item
+   * @param reference
+   * @param tag
+   * @param direction Must be either To or Bidirectional
+   * @returns
+   */
+  async insertBefore(
+    collection: NodeId,
+    item: NodeId,
+    reference?: NodeId,
+    tag?: Tag,
+    direction: LinkDirection = LinkDirection.To
+  ): Promise<NodeLinkMeta> {
+    const input: InsertItemInput = {
+      collection,
+      item,
+      tag,
+      direction: linkDirectionToRustFormat(direction),
+      reference,
+    };
+    return this.callZome("insert_before", input);
+  }
+
+  /**
+   * Moves an item of an ordered collection right after the given item,
+   * or to the start if no item is given. Only the link of the moved item
+   * gets replaced.
+   *
+   * @param collection
+   * @param item
+   * @param after
+   * @param tag
+   * @returns
+   */
+  async moveItem(
+    collection: NodeId,
+    item: NodeId,
+    after?: NodeId,
+    tag?: Tag
+  ): Promise<NodeLinkMeta> {
+    const input: MoveItemInput = { collection, item, tag, after };
+    return this.callZome("move_item", input);
+  }
+
+  /**
+   * Gets the maximum size in bytes of a tag on a link pointing to the
+   * given node
+   *
+   * @param targetNodeId
+   * @returns
+   */
+  async maxUserTagSize(targetNodeId: NodeId): Promise<number> {
+    return this.callZome("max_user_tag_size", targetNodeId);
+  }
+
+  /**
+   * Walks the links from the start node breadth-first up to maxDepth hops
+   * and returns the visited nodes together with the links between them
+   *
+   * @param start
+   * @param maxDepth
+   * @param filters
+   * @returns
+   */
+  async traverse(
+    start: NodeId,
+    maxDepth: number,
+    filters?: TraverseFilters
+  ): Promise<Subgraph> {
+    const input: TraverseInput = { start, max_depth: maxDepth, filters };
+    return this.callZome("traverse", input);
+  }
+
+  /**
+   * Finds a shortest path of at most maxDepth links between two nodes.
+   * Links are followed regardless of their direction.
+   *
+   * @param from
+   * @param to
+   * @param maxDepth
+   * @param allowedTags If provided, only links with one of these tags are followed
+   * @returns The path or undefined if there is none
+   */
+  async findPath(
+    from: NodeId,
+    to: NodeId,
+    maxDepth: number,
+    allowedTags?: Tag[]
+  ): Promise<GraphPath | undefined> {
+    const input: FindPathInput = {
+      from,
+      to,
+      max_depth: maxDepth,
+      allowed_tags: allowedTags,
+    };
+    return this.callZome("find_path", input);
+  }
+
+  /**
+   * Finds all matches of a graph pattern, e.g.
+   * `(anchor "projects")-[tag "task"]->(t:thing)-[tag "assignee"]->(agent me)`
+   *
+   * Nodes are written as `([variable:]kind [id])` with kind being one of
+   * anchor, agent, thing or node and edges as `-[filter]->` or
+   * `<-[filter]-` with an optional `tag "..."` or `prefix "..."` filter.
+   * The first node needs an id.
+   *
+   * @param query
+   * @returns The variable names and one row of bound nodes per match
+   */
+  async queryGraph(query: string): Promise<QueryGraphResult> {
+    return this.callZome("query_graph", query);
+  }
+
+  /**
+   * Gets the node content and linked node ids for for the given node id
+   *
+   * @param nodeId
+   * @param expand If provided, the content of the linked nodes is returned
+   * as well, down to the given depth
+   * @param getStrategy
+   * @param includeTrashed whether to return things that are in the trash
    * @returns
    */
   async getNodeAndLinkedNodeIds(
-    nodeId: NodeId
+    nodeId: NodeId,
+    expand?: ExpandInput,
+    getStrategy?: GetStrategy,
+    includeTrashed = false
   ): Promise<NodeAndLinkedIds | undefined> {
-    return this.callZome("get_node_and_linked_node_ids", nodeId);
+    const input: GetNodeAndLinkedNodeIdsInput = {
+      node_id: nodeId,
+      expand,
+      get_strategy: getStrategy,
+      include_trashed: includeTrashed,
+    };
+    return this.callZome("get_node_and_linked_node_ids", input);
   }
 
   /**
@@ -779,12 +1325,79 @@ export class SimpleHolochain {
    * zome call
    *
    * @param nodeIds
+   * @param expand If provided, the content of the linked nodes is returned
+   * as well, down to the given depth
+   * @param getStrategy
+   * @param includeTrashed whether to return things that are in the trash
    * @returns
    */
   async batchGetNodeAndLinkedNodeIds(
-    nodeIds: NodeId[]
+    nodeIds: NodeId[],
+    expand?: ExpandInput,
+    getStrategy?: GetStrategy,
+    includeTrashed = false
   ): Promise<NodeAndLinkedIds[]> {
-    return this.callZome("batch_get_node_and_linked_node_ids", nodeIds);
+    const input: BatchGetNodeAndLinkedNodeIdsInput = {
+      node_ids: nodeIds,
+      expand,
+      get_strategy: getStrategy,
+      include_trashed: includeTrashed,
+    };
+    return this.callZome("batch_get_node_and_linked_node_ids", input);
+  }
+
+  /**
+   * Gets only what changed for the given nodes since the given timestamp,
+   * i.e. the links created and deleted and the Things updated or deleted.
+   * Pass the returned high_water_mark as since to the next call.
+   *
+   * @param nodeIds
+   * @param since
+   * @param getStrategy
+   * @returns
+   */
+  async getNodeChangesSince(
+    nodeIds: NodeId[],
+    since: number,
+    getStrategy?: GetStrategy
+  ): Promise<NodeChangesSince> {
+    const input: GetNodeChangesSinceInput = {
+      node_ids: nodeIds,
+      since,
+      get_strategy: getStrategy,
+    };
+    return this.callZome("get_node_changes_since", input);
+  }
+
+  /**
+   * Subscribes the current agent to the changes of a node. Subscribers
+   * get remote signals whenever the node or its links change.
+   *
+   * @param nodeId
+   * @returns the link from the node to the current agent
+   */
+  async subscribe(nodeId: NodeId): Promise<NodeLinkMeta> {
+    return this.callZome("subscribe", nodeId);
+  }
+
+  /**
+   * Removes the subscriptions of the current agent to a node
+   *
+   * @param nodeId
+   * @returns
+   */
+  async unsubscribe(nodeId: NodeId): Promise<void> {
+    return this.callZome("unsubscribe", nodeId);
+  }
+
+  /**
+   * Gets the agents subscribed to a node
+   *
+   * @param nodeId
+   * @returns
+   */
+  async getSubscribers(nodeId: NodeId): Promise<AgentPubKey[]> {
+    return this.callZome("get_subscribers", nodeId);
   }
 
   /**
@@ -831,6 +1444,19 @@ export class SimpleHolochain {
   }
 }
 
+function linkDirectionToRustFormat(
+  direction: LinkDirection
+): LinkDirectionRust {
+  switch (direction) {
+    case LinkDirection.From:
+      return { type: "From" };
+    case LinkDirection.To:
+      return { type: "To" };
+    case LinkDirection.Bidirectional:
+      return { type: "Bidirectional" };
+  }
+}
+
 export function linkInputToRustFormat(linkInput: LinkInput): LinkInputRust {
   let linkDirection: LinkDirectionRust;
   switch (linkInput.direction) {
@@ -847,6 +1473,9 @@ export function linkInputToRustFormat(linkInput: LinkInput): LinkInputRust {
     direction: linkDirection,
     node_id: linkInput.node_id,
     tag: linkInput.tag,
+    dedupe: linkInput.dedupe,
+    position: linkInput.position,
+    expires_at: linkInput.expires_at,
   };
 }
 
@@ -1,6 +1,7 @@
 import {
   ActionHash,
   AgentPubKey,
+  AnyDhtHash,
   Create,
   CreateLink,
   Delete,
@@ -74,6 +75,22 @@ export type RemoteSignalInput = {
   agents: AgentPubKey[];
 };
 
+/**
+ * DNA properties configuring the remote signals that the zome sends to the
+ * interested agents after each commit
+ */
+export type RemoteSignalProperties = {
+  /** Maximum number of agents each signal is sent to. Defaults to 50. */
+  remote_signal_fan_out_limit?: number;
+  /**
+   * Whether to also send signals to all agents. Defaults to false, so only
+   * the agents interested in the touched nodes are signaled. Only enable it
+   * in small networks, as every commit then fetches the links to all
+   * agents.
+   */
+  remote_signal_all_agents?: boolean;
+};
+
 /* dprint-ignore-start */
 export type EntryTypes = { type: "Thing" } & ThingEntry;
 /* dprint-ignore-end */
@@ -99,6 +116,13 @@ export type LinkTagContent = {
   /// If it's a link pointing to a Thing then this contains the creator's
   /// public key
   thing_created_by: AgentPubKey | undefined;
+  /// If the link is part of an ordered collection, this contains its
+  /// fractional index key
+  position: string | undefined;
+  /// If set, the link is treated as absent from this time on
+  expires_at: number | undefined;
+  /// If the base of the link is an anchor, this contains the anchor string
+  source_anchor: string | undefined;
 };
 
 export type NodeLinkMeta = {
@@ -174,6 +198,41 @@ export type Thing = {
   creator: AgentPubKey;
   created_at: number;
   updated_at: number;
+  /**
+   * Set if the Thing is known to be deleted. Only filled in by reads that
+   * look at the details of a Thing, like get_thing_details.
+   */
+  deleted?: ThingDeletion;
+  /**
+   * Time (in microseconds) from which on the Thing is treated as absent
+   */
+  expires_at?: number;
+};
+
+export type ThingDeletion = {
+  by: AgentPubKey;
+  at: number;
+};
+
+export type ThingStatus =
+  | { type: "Live" }
+  | { type: "Deleted"; by: AgentPubKey; at: number }
+  /**
+   * The latest version of the Thing has expired, so it is treated as absent
+   * even though it has not been deleted (yet)
+   */
+  | { type: "Expired"; at: number }
+  /**
+   * Either the Thing never existed or it has not reached this agent yet
+   */
+  | { type: "NotFound" };
+
+export type ThingDetails = {
+  status: ThingStatus;
+  /**
+   * All versions of the Thing, starting with the original one
+   */
+  revisions: Thing[];
 };
 
 export type ThingId = ActionHash;
@@ -188,6 +247,22 @@ export type LinkInput = {
   direction: LinkDirection;
   node_id: NodeId;
   tag?: Uint8Array;
+  /**
+   * If true, no new link is created if a link with the same tag
+   * already exists between the two nodes
+   */
+  dedupe?: boolean;
+  /**
+   * Fractional index key of the link if it is part of an ordered
+   * collection. Use insertAfter, insertBefore or moveItem to have
+   * it generated.
+   */
+  position?: string;
+  /**
+   * Time (in microseconds) from which on the link is treated as absent.
+   * Expired links are deleted by the scheduled cleanup of their author.
+   */
+  expires_at?: number;
 };
 
 export type LinkDirectionRust =
@@ -205,11 +280,81 @@ export type LinkInputRust = {
   direction: LinkDirectionRust;
   node_id: NodeId;
   tag?: Uint8Array;
+  dedupe?: boolean;
+  position?: string;
+  expires_at?: number;
+};
+
+export type HasLinkInput = {
+  src: NodeId;
+  dst: NodeId;
+  tag?: Uint8Array;
+};
+
+export type InsertItemInput = {
+  collection: NodeId;
+  item: NodeId;
+  tag?: Uint8Array;
+  /**
+   * Must be either To or Bidirectional
+   */
+  direction: LinkDirectionRust;
+  /**
+   * The item to insert next to. If undefined, the item is inserted at
+   * the end (insert_after) or at the start (insert_before)
+   */
+  reference?: NodeId;
+};
+
+export type MoveItemInput = {
+  collection: NodeId;
+  item: NodeId;
+  tag?: Uint8Array;
+  /**
+   * The item to move the item after. If undefined, the item is moved
+   * to the start of the collection.
+   */
+  after?: NodeId;
 };
 
 export type CreateThingInput = {
   content: string;
   links?: LinkInputRust[];
+  /**
+   * Time (in microseconds) from which on the Thing is treated as absent.
+   * Expired Things are deleted by the scheduled cleanup of their author.
+   */
+  expires_at?: number;
+};
+
+export type OrphanAction =
+  /**
+   * Links every orphan from the given anchor
+   */
+  | { type: "Relink"; anchor: string }
+  /**
+   * Deletes the orphans with the given ids like deleteThing does, i.e.
+   * together with all links connected to them that the calling agent can
+   * find. One-directional links from other
+   * agents can't be seen, so the ids should come from a previous run that
+   * only reported the orphans and have been confirmed by the user.
+   */
+  | { type: "Delete"; thing_ids: ThingId[] };
+
+export type FindMyOrphansInput = {
+  /**
+   * What to do with the orphans. If undefined they are only returned.
+   */
+  action?: OrphanAction;
+  get_strategy?: GetStrategy;
+};
+
+export type DeletedExpired = {
+  thing_ids: ThingId[];
+  /**
+   * Create action hashes of the deleted links
+   */
+  link_action_hashes: ActionHash[];
 };
 
 export type UpdateThingInput = {
@@ -229,7 +374,375 @@ export type CreateOrDeleteLinksInput = {
   links: LinkInputRust[];
 };
 
+/**
+ * Filters that are applied by the DHT authorities when fetching the
+ * links from a node
+ */
+export type LinkedNodesFilter = {
+  /**
+   * If provided, only links whose tag starts with these bytes are fetched
+   */
+  tag_prefix?: Uint8Array;
+  /**
+   * If provided, only links created after this time are fetched
+   */
+  after?: number;
+  /**
+   * If provided, only links created before this time are fetched
+   */
+  before?: number;
+  /**
+   * If provided, only links created by this agent are fetched
+   */
+  author?: AgentPubKey;
+};
+
+export type SortKey =
+  | { type: "CreatedAt" }
+  | { type: "UpdatedAt" }
+  | { type: "LinkTimestamp" }
+  | { type: "Creator" }
+  /**
+   * The tag compared byte by byte after skipping the first `skip` bytes
+   */
+  | { type: "TagString"; skip: number }
+  /**
+   * The tag parsed as a UTF-8 encoded decimal number after skipping the
+   * first `skip` bytes
+   */
+  | { type: "TagNumber"; skip: number }
+  /**
+   * The position of the link in an ordered collection
+   */
+  | { type: "Position" };
+
+export type SortBy = {
+  key: SortKey;
+  order: SortOrder;
+};
+
+/**
+ * Whether to fetch from the network or only from the local databases.
+ * Local returns cached data without waiting for other peers.
+ */
+export type GetStrategy = "Local" | "Network";
+
+export type GetLinkedNodesInput = {
+  node_id: NodeId;
+  /**
+   * Only supported by get_linked_things and get_all_linked_nodes, the
+   * other externs fail if it is set
+   */
+  sort?: SortBy;
+  /**
+   * Defaults to Network
+   */
+  get_strategy?: GetStrategy;
+  /**
+   * Whether to return linked Things that have been moved to the trash. Not
+   * supported by get_linked_agents and get_linked_anchors.
+   */
+  include_trashed?: boolean;
+} & LinkedNodesFilter;
+
+export type GetRecordsInput = {
+  hashes: AnyDhtHash[];
+  get_strategy?: GetStrategy;
+};
+
+export type GetThingInput = {
+  thing_id: ThingId;
+  get_strategy?: GetStrategy;
+  /**
+   * Not supported by get_thing_details, which returns trashed things
+   */
+  include_trashed?: boolean;
+};
+
+export type GetThingsInput = {
+  thing_ids: ThingId[];
+  get_strategy?: GetStrategy;
+  include_trashed?: boolean;
+};
+
+export type TrashThingInput = {
+  thing_id: ThingId;
+  /**
+   * Whether to use the global trash instead of the trash of the
+   * calling agent
+   */
+  global: boolean;
+};
+
+export type TrashInput = {
+  global: boolean;
+};
+
+/**
+ * Position in a list of links ordered by link timestamp and create link hash
+ */
+export type LinkCursor = {
+  timestamp: number;
+  create_link_hash: ActionHash;
+};
+
+export type SortOrder = { type: "Ascending" } | { type: "Descending" };
+
+export type GetLinkedNodesPageInput = {
+  query: GetLinkedNodesInput;
+  limit: number;
+  cursor?: LinkCursor;
+  order: SortOrder;
+};
+
+export type Page<T> = {
+  items: T[];
+  next_cursor: LinkCursor | undefined;
+};
+
+export type NodeLinkType = "ToThing" | "ToAgent" | "ToAnchor";
+
+export type CountLinksInput = {
+  node_id: NodeId;
+  link_type?: NodeLinkType;
+  tag_prefix?: Uint8Array;
+};
+
+/**
+ * Number of links from a node per link type. Undefined for link
+ * types that were not counted.
+ */
+export type LinkCounts = {
+  to_thing: number | undefined;
+  to_agent: number | undefined;
+  to_anchor: number | undefined;
+};
+
+export type GetLinkHistoryInput = {
+  node_id: NodeId;
+  /**
+   * If not provided, links of all node link types are returned
+   */
+  link_type?: NodeLinkType;
+  get_strategy?: GetStrategy;
+};
+
+export type LinkDeletion = {
+  delete_action_hash: ActionHash;
+  deleted_by: AgentPubKey;
+  deleted_at: number;
+};
+
+export type LinkHistoryEntry = {
+  link: NodeLinkMeta;
+  created_by: AgentPubKey;
+  created_at: number;
+  /**
+   * Empty if the link is live
+   */
+  deletes: LinkDeletion[];
+};
+
+export type RepairGraphInput = {
+  node_ids: NodeId[];
+  /**
+   * Whether to delete the broken links authored by the current agent.
+   * Otherwise all issues are only reported.
+   */
+  fix: boolean;
+  get_strategy?: GetStrategy;
+};
+
+export type GraphIssueKind =
+  /**
+   * A link of a bidirectional pair whose backlink has been deleted or
+   * cannot be found
+   */
+  | { type: "MissingBacklink" }
+  /**
+   * The backlink of a bidirectional pair whose link has been deleted
+   */
+  | { type: "OrphanedBacklink" }
+  /**
+   * A link pointing to a Thing that has been deleted
+   */
+  | { type: "LinkToDeletedThing" };
+
+export type GraphIssue = {
+  kind: GraphIssueKind;
+  link: NodeLinkMeta;
+  /**
+   * Whether the broken link has been deleted. Only links authored by the
+   * current agent are deleted, and only if the issue is certain.
+   */
+  repaired: boolean;
+};
+
 export type NodeAndLinkedIds = {
   content: NodeContent;
   linked_node_ids: NodeIdAndMetaTag[];
+  /**
+   * The hydrated linked nodes if expand was requested
+   */
+  expanded: ExpandedNode[] | undefined;
+};
+
+export type ExpandInput = {
+  /**
+   * How many hops of linked nodes to hydrate
+   */
+  depth: number;
+  tag_prefix?: Uint8Array;
+};
+
+export type ExpandedNode = {
+  node_id: NodeId;
+  meta_tag: LinkTagContent;
+  content: NodeContent;
+  /**
+   * Empty at the maximum depth and for nodes that have already been
+   * expanded elsewhere in the tree
+   */
+  linked_nodes: ExpandedNode[];
+};
+
+export type GetNodeAndLinkedNodeIdsInput = {
+  node_id: NodeId;
+  expand?: ExpandInput;
+  get_strategy?: GetStrategy;
+  /**
+   * Whether to return Things that have been moved to the trash
+   */
+  include_trashed?: boolean;
+};
+
+export type BatchGetNodeAndLinkedNodeIdsInput = {
+  node_ids: NodeId[];
+  expand?: ExpandInput;
+  get_strategy?: GetStrategy;
+  /**
+   * Whether to return Things that have been moved to the trash
+   */
+  include_trashed?: boolean;
+};
+
+export type GetNodeChangesSinceInput = {
+  node_ids: NodeId[];
+  /**
+   * Only changes after this timestamp are returned. Pass the
+   * high_water_mark of the previous call.
+   */
+  since: number;
+  get_strategy?: GetStrategy;
+};
+
+export type NodeChanges = {
+  node_id: NodeId;
+  /**
+   * Links created after since that are not deleted
+   */
+  links_created: NodeLinkMeta[];
+  links_deleted: NodeLinkMeta[];
+  /**
+   * The latest version of the Thing if it has been updated after since
+   */
+  thing_updated: Thing | undefined;
+  thing_deleted: boolean;
+};
+
+export type NodeChangesSince = {
+  /**
+   * Nodes without changes are left out
+   */
+  changes: NodeChanges[];
+  /**
+   * The latest timestamp of all returned changes, or since if there are none
+   */
+  high_water_mark: number;
+};
+
+export type TraversalDirection = { type: "Outgoing" } | { type: "Incoming" };
+
+export type HopFilter = {
+  tag_prefix?: Uint8Array;
+};
+
+export type TraverseFilters = {
+  /**
+   * Filter per hop, i.e. hops[0] applies to the links of the start node,
+   * hops[1] to the links of its neighbours and so on
+   */
+  hops?: HopFilter[];
+  /**
+   * Defaults to Outgoing. Incoming follows the links pointing to a node,
+   * which is only possible for links that have a backlink or were created
+   * in the From direction.
+   */
+  direction?: TraversalDirection;
+  /**
+   * If provided, only nodes of the respective types are visited
+   */
+  link_types?: NodeLinkType[];
+  /**
+   * Maximum number of nodes to visit, including the start node
+   */
+  max_nodes?: number;
+};
+
+export type TraverseInput = {
+  start: NodeId;
+  max_depth: number;
+  filters?: TraverseFilters;
+};
+
+export type TraversedNode = {
+  node_id: NodeId;
+  depth: number;
+};
+
+export type Subgraph = {
+  nodes: TraversedNode[];
+  edges: NodeLinkMeta[];
+  /**
+   * Whether the traversal stopped early because max_nodes was reached
+   */
+  truncated: boolean;
+};
+
+export type FindPathInput = {
+  from: NodeId;
+  to: NodeId;
+  /**
+   * Maximum number of links in the path
+   */
+  max_depth: number;
+  /**
+   * If provided, only links whose tag is one of these are followed
+   */
+  allowed_tags?: Uint8Array[];
+};
+
+export type GraphPath = {
+  /**
+   * The nodes of the path, starting with from and ending with to
+   */
+  nodes: NodeId[];
+  /**
+   * links[i] connects nodes[i] and nodes[i + 1]. A link may point in
+   * either direction.
+   */
+  links: NodeLinkMeta[];
+};
+
+export type QueryGraphResult = {
+  /**
+   * The variable names of the nodes of the pattern in order. Nodes
+   * without a variable are named by their index, e.g. "_0".
+   */
+  variables: string[];
+  /**
+   * One row per match with the bound node of every variable in the
+   * order of variables
+   */
+  rows: NodeId[][];
 };
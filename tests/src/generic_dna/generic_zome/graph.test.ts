@@ -0,0 +1,471 @@
+import { assert, test } from "vitest";
+
+import { encodeHashToBase64 } from "@holochain/client";
+import { AppWithOptions, runScenario } from "@holochain/tryorama";
+
+import { getCellByRoleName } from "./common.js";
+import {
+  ExpandedNode,
+  GraphPath,
+  LinkDirection,
+  linkInputToRustFormat,
+  NodeAndLinkedIds,
+  NodeId,
+  QueryGraphResult,
+  Subgraph,
+  Thing,
+} from "@holochain/simple-holochain";
+
+const nodeIdToString = (nodeId: NodeId) =>
+  nodeId.type === "Anchor" ? nodeId.id : encodeHashToBase64(nodeId.id);
+
+test("Traverse a tree of Things breadth-first with per-hop filters and a node budget", async () => {
+  await runScenario(async (scenario) => {
+    const testAppPath = process.cwd() + "/../workdir/generic-dna.happ";
+    const appSource: AppWithOptions = {
+      appBundleSource: { type: "path", value: testAppPath },
+    };
+    const [alice] = await scenario.addPlayersWithApps([appSource]);
+    const aliceCell = getCellByRoleName(alice, "generic_dna");
+
+    const textEncoder = new TextEncoder();
+    const anchor: NodeId = { type: "Anchor", id: "PROJECTS" };
+    const createThing = async (
+      content: string,
+      parent: NodeId,
+      tag: string
+    ): Promise<NodeId> => {
+      const thing: Thing = await aliceCell.callZome({
+        zome_name: "generic_zome",
+        fn_name: "create_thing",
+        payload: {
+          content,
+          links: [
+            linkInputToRustFormat({
+              direction: LinkDirection.From,
+              node_id: parent,
+              tag: textEncoder.encode(tag),
+            }),
+          ],
+        },
+      });
+      return { type: "Thing", id: thing.id };
+    };
+    const project = await createThing("project", anchor, "project");
+    const task = await createThing("task", project, "task");
+    const subtask = await createThing("subtask", task, "task");
+    await createThing("comment", project, "comment");
+
+    const subgraph: Subgraph = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "traverse",
+      payload: {
+        start: anchor,
+        max_depth: 2,
+        filters: {
+          hops: [{}, { tag_prefix: textEncoder.encode("task") }],
+        },
+      },
+    });
+    assert.deepEqual(
+      subgraph.nodes.map((n) => [nodeIdToString(n.node_id), n.depth]),
+      [
+        [nodeIdToString(anchor), 0],
+        [nodeIdToString(project), 1],
+        [nodeIdToString(task), 2],
+      ]
+    );
+    assert.equal(subgraph.edges.length, 2);
+    assert.isFalse(subgraph.truncated);
+
+    const deepSubgraph: Subgraph = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "traverse",
+      payload: {
+        start: anchor,
+        max_depth: 10,
+        filters: { max_nodes: 3 },
+      },
+    });
+    assert.equal(deepSubgraph.nodes.length, 3);
+    assert.isTrue(deepSubgraph.truncated);
+    assert.notInclude(
+      deepSubgraph.nodes.map((n) => nodeIdToString(n.node_id)),
+      nodeIdToString(subtask)
+    );
+  });
+});
+
+test("Traverse the links pointing to a Thing", async () => {
+  await runScenario(async (scenario) => {
+    const testAppPath = process.cwd() + "/../workdir/generic-dna.happ";
+    const appSource: AppWithOptions = {
+      appBundleSource: { type: "path", value: testAppPath },
+    };
+    const [alice] = await scenario.addPlayersWithApps([appSource]);
+    const aliceCell = getCellByRoleName(alice, "generic_dna");
+
+    const textEncoder = new TextEncoder();
+    const createThing = async (
+      content: string,
+      direction: LinkDirection,
+      nodeId?: NodeId
+    ): Promise<NodeId> => {
+      const thing: Thing = await aliceCell.callZome({
+        zome_name: "generic_zome",
+        fn_name: "create_thing",
+        payload: {
+          content,
+          links: nodeId
+            ? [
+                linkInputToRustFormat({
+                  direction,
+                  node_id: nodeId,
+                  tag: textEncoder.encode(content),
+                }),
+              ]
+            : [],
+        },
+      });
+      return { type: "Thing", id: thing.id };
+    };
+    const project = await createThing("project", LinkDirection.To);
+    // Stored at the project, pointing to the task
+    const task = await createThing("task", LinkDirection.From, project);
+    // Stored at the note with a backlink stored at the project
+    const note = await createThing(
+      "note",
+      LinkDirection.Bidirectional,
+      project
+    );
+    // Stored at the draft only, so not visible from the project
+    await createThing("draft", LinkDirection.To, project);
+
+    const subgraph: Subgraph = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "traverse",
+      payload: {
+        start: project,
+        max_depth: 1,
+        filters: { direction: { type: "Incoming" } },
+      },
+    });
+    assert.sameMembers(
+      subgraph.nodes.map((n) => nodeIdToString(n.node_id)),
+      [project, task, note].map(nodeIdToString)
+    );
+    assert.equal(subgraph.edges.length, 2);
+    for (const edge of subgraph.edges) {
+      assert.equal(nodeIdToString(edge.dst), nodeIdToString(project));
+    }
+    const noteEdge = subgraph.edges.find(
+      (e) => nodeIdToString(e.src) === nodeIdToString(note)
+    )!;
+    // The edge of a bidirectional link is its forward link
+    assert.exists(noteEdge.meta_tag.backlink_action_hash);
+    assert.equal(new TextDecoder().decode(noteEdge.meta_tag.tag!), "note");
+  });
+});
+
+test("Find a shortest path between an agent and a Thing", async () => {
+  await runScenario(async (scenario) => {
+    const testAppPath = process.cwd() + "/../workdir/generic-dna.happ";
+    const appSource: AppWithOptions = {
+      appBundleSource: { type: "path", value: testAppPath },
+    };
+    const [alice] = await scenario.addPlayersWithApps([appSource]);
+    const aliceCell = getCellByRoleName(alice, "generic_dna");
+
+    const textEncoder = new TextEncoder();
+    const agent: NodeId = { type: "Agent", id: alice.agentPubKey };
+    const project: Thing = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "create_thing",
+      payload: {
+        content: "project",
+        links: [
+          linkInputToRustFormat({
+            direction: LinkDirection.Bidirectional,
+            node_id: agent,
+            tag: textEncoder.encode("member"),
+          }),
+        ],
+      },
+    });
+    const projectNode: NodeId = { type: "Thing", id: project.id };
+    const task: Thing = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "create_thing",
+      payload: {
+        content: "task",
+        links: [
+          linkInputToRustFormat({
+            direction: LinkDirection.Bidirectional,
+            node_id: projectNode,
+            tag: textEncoder.encode("task"),
+          }),
+        ],
+      },
+    });
+    const taskNode: NodeId = { type: "Thing", id: task.id };
+
+    const path: GraphPath | undefined = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "find_path",
+      payload: { from: agent, to: taskNode, max_depth: 3 },
+    });
+    assert.ok(path);
+    assert.deepEqual(path!.nodes.map(nodeIdToString), [
+      nodeIdToString(agent),
+      nodeIdToString(projectNode),
+      nodeIdToString(taskNode),
+    ]);
+    assert.equal(path!.links.length, 2);
+
+    const tooShort: GraphPath | undefined = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "find_path",
+      payload: { from: agent, to: taskNode, max_depth: 1 },
+    });
+    assert.notExists(tooShort);
+
+    const onlyMembers: GraphPath | undefined = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "find_path",
+      payload: {
+        from: agent,
+        to: taskNode,
+        max_depth: 3,
+        allowed_tags: [textEncoder.encode("member")],
+      },
+    });
+    assert.notExists(onlyMembers);
+  });
+});
+
+test("Find a path to a node without links of its own", async () => {
+  await runScenario(async (scenario) => {
+    const testAppPath = process.cwd() + "/../workdir/generic-dna.happ";
+    const appSource: AppWithOptions = {
+      appBundleSource: { type: "path", value: testAppPath },
+    };
+    const [alice] = await scenario.addPlayersWithApps([appSource]);
+    const aliceCell = getCellByRoleName(alice, "generic_dna");
+
+    // A -> B -> C and A -> D, with C being a sink that has no links stored
+    // at it, so the search from C runs out of nodes right away
+    const a: NodeId = { type: "Anchor", id: "PATH_START" };
+    const c: NodeId = { type: "Anchor", id: "PATH_SINK" };
+    const b: Thing = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "create_thing",
+      payload: {
+        content: "b",
+        links: [
+          linkInputToRustFormat({ direction: LinkDirection.From, node_id: a }),
+          linkInputToRustFormat({ direction: LinkDirection.To, node_id: c }),
+        ],
+      },
+    });
+    await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "create_thing",
+      payload: {
+        content: "d",
+        links: [
+          linkInputToRustFormat({ direction: LinkDirection.From, node_id: a }),
+        ],
+      },
+    });
+
+    const path: GraphPath | undefined = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "find_path",
+      payload: { from: a, to: c, max_depth: 2 },
+    });
+    assert.ok(path);
+    assert.deepEqual(path!.nodes.map(nodeIdToString), [
+      nodeIdToString(a),
+      encodeHashToBase64(b.id),
+      nodeIdToString(c),
+    ]);
+  });
+});
+
+test("Query the graph with a pattern", async () => {
+  await runScenario(async (scenario) => {
+    const testAppPath = process.cwd() + "/../workdir/generic-dna.happ";
+    const appSource: AppWithOptions = {
+      appBundleSource: { type: "path", value: testAppPath },
+    };
+    const [alice] = await scenario.addPlayersWithApps([appSource]);
+    const aliceCell = getCellByRoleName(alice, "generic_dna");
+
+    const textEncoder = new TextEncoder();
+    const anchor: NodeId = { type: "Anchor", id: "projects" };
+    const agent: NodeId = { type: "Agent", id: alice.agentPubKey };
+    const createTask = async (content: string, assigned: boolean) => {
+      const task: Thing = await aliceCell.callZome({
+        zome_name: "generic_zome",
+        fn_name: "create_thing",
+        payload: {
+          content,
+          links: [
+            linkInputToRustFormat({
+              direction: LinkDirection.From,
+              node_id: anchor,
+              tag: textEncoder.encode("task"),
+            }),
+          ],
+        },
+      });
+      const taskNode: NodeId = { type: "Thing", id: task.id };
+      if (assigned) {
+        await aliceCell.callZome({
+          zome_name: "generic_zome",
+          fn_name: "create_links_from_node",
+          payload: {
+            src: taskNode,
+            links: [
+              linkInputToRustFormat({
+                direction: LinkDirection.To,
+                node_id: agent,
+                tag: textEncoder.encode("assignee"),
+              }),
+            ],
+          },
+        });
+      }
+      return taskNode;
+    };
+    const assignedTask = await createTask("assigned", true);
+    await createTask("unassigned", false);
+
+    const result: QueryGraphResult = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "query_graph",
+      payload:
+        '(anchor "projects")-[tag "task"]->(t:thing)-[tag "assignee"]->(agent me)',
+    });
+    assert.deepEqual(result.variables, ["_0", "t", "_2"]);
+    assert.equal(result.rows.length, 1);
+    assert.equal(
+      nodeIdToString(result.rows[0][1]),
+      nodeIdToString(assignedTask)
+    );
+
+    try {
+      await aliceCell.callZome({
+        zome_name: "generic_zome",
+        fn_name: "query_graph",
+        payload: "(thing)-[]->(agent)",
+      });
+      assert.fail("query without a start id should fail");
+    } catch (e) {
+      assert.include(String(e), "needs an id");
+    }
+  });
+});
+
+test("Get a node together with its linked nodes hydrated down to a given depth", async () => {
+  await runScenario(async (scenario) => {
+    const testAppPath = process.cwd() + "/../workdir/generic-dna.happ";
+    const appSource: AppWithOptions = {
+      appBundleSource: { type: "path", value: testAppPath },
+    };
+    const [alice] = await scenario.addPlayersWithApps([appSource]);
+    const aliceCell = getCellByRoleName(alice, "generic_dna");
+
+    const textEncoder = new TextEncoder();
+    const anchor: NodeId = { type: "Anchor", id: "DETAILS" };
+    let parent = anchor;
+    for (const content of ["project", "task", "subtask"]) {
+      const thing: Thing = await aliceCell.callZome({
+        zome_name: "generic_zome",
+        fn_name: "create_thing",
+        payload: {
+          content,
+          links: [
+            linkInputToRustFormat({
+              direction: LinkDirection.From,
+              node_id: parent,
+              tag: textEncoder.encode("child"),
+            }),
+          ],
+        },
+      });
+      parent = { type: "Thing", id: thing.id };
+    }
+
+    const result: NodeAndLinkedIds = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_node_and_linked_node_ids",
+      payload: {
+        node_id: anchor,
+        expand: { depth: 2, tag_prefix: textEncoder.encode("child") },
+      },
+    });
+    assert.equal(result.linked_node_ids.length, 1);
+    const [project] = result.expanded!;
+    assert.equal(
+      project.content.type === "Thing" && project.content.content.content,
+      "project"
+    );
+    const [task] = project.linked_nodes;
+    assert.equal(
+      task.content.type === "Thing" && task.content.content.content,
+      "task"
+    );
+    assert.deepEqual(task.linked_nodes, []);
+
+    const [withoutExpand]: NodeAndLinkedIds[] = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "batch_get_node_and_linked_node_ids",
+      payload: { node_ids: [anchor] },
+    });
+    assert.notExists(withoutExpand.expanded);
+
+    // A node linked at several depths is expanded at the lowest one: x is
+    // linked from the anchor directly and through p
+    const shared: NodeId = { type: "Anchor", id: "SHARED" };
+    const createChild = async (
+      content: string,
+      links: { direction: LinkDirection; node_id: NodeId }[]
+    ): Promise<NodeId> => {
+      const thing: Thing = await aliceCell.callZome({
+        zome_name: "generic_zome",
+        fn_name: "create_thing",
+        payload: {
+          content,
+          links: links.map((link) =>
+            linkInputToRustFormat({ ...link, tag: textEncoder.encode("child") })
+          ),
+        },
+      });
+      return { type: "Thing", id: thing.id };
+    };
+    const x = await createChild("x", [
+      { direction: LinkDirection.From, node_id: shared },
+    ]);
+    await createChild("y", [{ direction: LinkDirection.From, node_id: x }]);
+    await createChild("p", [
+      { direction: LinkDirection.From, node_id: shared },
+      { direction: LinkDirection.To, node_id: x },
+    ]);
+    const sharedResult: NodeAndLinkedIds = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_node_and_linked_node_ids",
+      payload: {
+        node_id: shared,
+        expand: { depth: 2, tag_prefix: textEncoder.encode("child") },
+      },
+    });
+    const contentOf = (node: ExpandedNode) =>
+      node.content.type === "Thing" && node.content.content.content;
+    const topX = sharedResult.expanded!.find((n) => contentOf(n) === "x")!;
+    assert.deepEqual(topX.linked_nodes.map(contentOf), ["y"]);
+    const topP = sharedResult.expanded!.find((n) => contentOf(n) === "p")!;
+    assert.deepEqual(topP.linked_nodes.map(contentOf), ["x"]);
+    assert.deepEqual(topP.linked_nodes[0].linked_nodes, []);
+  });
+});
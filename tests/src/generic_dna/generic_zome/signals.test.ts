@@ -0,0 +1,307 @@
+import { assert, test } from "vitest";
+
+import {
+  AgentPubKey,
+  encodeHashToBase64,
+  Signal,
+  SignalType,
+} from "@holochain/client";
+import {
+  AppWithOptions,
+  dhtSync,
+  pause,
+  runScenario,
+} from "@holochain/tryorama";
+
+import { getCellByRoleName } from "./common.js";
+import {
+  GenericZomeSignal,
+  LinkDirection,
+  LinkCounts,
+  linkInputToRustFormat,
+  LinkTagContent,
+  NodeId,
+  NodeIdAndMetaTag,
+  SignalKind,
+  Thing,
+} from "@holochain/simple-holochain";
+
+test("Emit signals for all committed actions in post_commit", async () => {
+  await runScenario(async (scenario) => {
+    const testAppPath = process.cwd() + "/../workdir/generic-dna.happ";
+    const appSource: AppWithOptions = {
+      appBundleSource: { type: "path", value: testAppPath },
+    };
+    const [alice] = await scenario.addPlayersWithApps([appSource]);
+    const aliceCell = getCellByRoleName(alice, "generic_dna");
+
+    const signals: SignalKind[] = [];
+    alice.appWs.on("signal", (signal: Signal) => {
+      if (signal.type !== SignalType.App) return;
+      const payload = signal.value.payload as GenericZomeSignal;
+      if (payload.type === "Local") signals.push(payload.content);
+    });
+
+    const thing: Thing = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "create_thing",
+      payload: {
+        content: "hello",
+        links: [
+          linkInputToRustFormat({
+            direction: LinkDirection.Bidirectional,
+            node_id: { type: "Anchor", id: "SIGNALS" },
+          }),
+        ],
+      },
+    });
+    await pause(500);
+    const created = signals.find((s) => s.type === "ThingCreated");
+    assert.equal(
+      created?.type === "ThingCreated" && encodeHashToBase64(created.thing.id),
+      encodeHashToBase64(thing.id)
+    );
+    const linksCreated = signals.find((s) => s.type === "LinksCreated");
+    assert.equal(
+      linksCreated?.type === "LinksCreated" && linksCreated.links.length,
+      2
+    );
+
+    await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "update_thing",
+      payload: { thing_id: thing.id, updated_content: "hello again" },
+    });
+    await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "delete_thing",
+      payload: {
+        thing_id: thing.id,
+        delete_backlinks: true,
+        delete_links_from_creator: false,
+      },
+    });
+    await pause(500);
+    const updated = signals.find((s) => s.type === "ThingUpdated");
+    assert.equal(
+      updated?.type === "ThingUpdated" && updated.thing.content,
+      "hello again"
+    );
+    const deleted = signals.find((s) => s.type === "ThingDeleted");
+    assert.equal(
+      deleted?.type === "ThingDeleted" && encodeHashToBase64(deleted.id),
+      encodeHashToBase64(thing.id)
+    );
+    const linksDeleted = signals.find((s) => s.type === "LinksDeleted");
+    assert.deepEqual(
+      linksDeleted?.type === "LinksDeleted" && linksDeleted.links[0].src,
+      { type: "Anchor", id: "SIGNALS" }
+    );
+
+    // Actions committed outside of the dedicated externs are signaled as well
+    signals.length = 0;
+    await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "add_agent_to_anchor",
+      payload: null,
+    });
+    await pause(500);
+    const agentLink = signals.find((s) => s.type === "LinksCreated");
+    assert.deepEqual(
+      agentLink?.type === "LinksCreated" && agentLink.links[0].src,
+      { type: "Anchor", id: "SIMPLE_HOLOCHAIN_ALL_AGENTS" }
+    );
+
+    // Links from anchors are signaled with the anchor as their source
+    signals.length = 0;
+    await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "create_links_from_node",
+      payload: {
+        src: { type: "Anchor", id: "SIGNALS_SRC" },
+        links: [
+          linkInputToRustFormat({
+            direction: LinkDirection.To,
+            node_id: { type: "Agent", id: alice.agentPubKey },
+          }),
+        ],
+      },
+    });
+    await pause(500);
+    const anchorLink = signals.find((s) => s.type === "LinksCreated");
+    assert.deepEqual(
+      anchorLink?.type === "LinksCreated" && anchorLink.links[0].src,
+      { type: "Anchor", id: "SIGNALS_SRC" }
+    );
+  });
+});
+
+test("Send remote signals only to the agents linked from touched nodes", async () => {
+  await runScenario(async (scenario) => {
+    const testAppPath = process.cwd() + "/../workdir/generic-dna.happ";
+    const appSource: AppWithOptions = {
+      appBundleSource: { type: "path", value: testAppPath },
+    };
+    const [alice, bob, carol] = await scenario.addPlayersWithApps([
+      appSource,
+      appSource,
+      appSource,
+    ]);
+    await scenario.shareAllAgents();
+    const aliceCell = getCellByRoleName(alice, "generic_dna");
+    const carolCell = getCellByRoleName(carol, "generic_dna");
+    // The first zome call runs init, which adds carol to the all agents
+    // anchor. That alone doesn't get her the signals.
+    await carolCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_subscribers",
+      payload: { type: "Anchor", id: "INIT" },
+    });
+    await dhtSync([alice, carol], aliceCell.cell_id[0]);
+
+    const remoteSignals = (player: typeof bob) => {
+      const signals: SignalKind[] = [];
+      player.appWs.on("signal", (signal: Signal) => {
+        if (signal.type !== SignalType.App) return;
+        const payload = signal.value.payload as GenericZomeSignal;
+        if (payload.type === "Remote") signals.push(payload.content);
+      });
+      return signals;
+    };
+    const bobSignals = remoteSignals(bob);
+    const carolSignals = remoteSignals(carol);
+
+    await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "create_thing",
+      payload: {
+        content: "for bob",
+        links: [
+          linkInputToRustFormat({
+            direction: LinkDirection.To,
+            node_id: { type: "Agent", id: bob.agentPubKey },
+          }),
+        ],
+      },
+    });
+    await pause(2000);
+
+    assert.isTrue(bobSignals.some((s) => s.type === "LinksCreated"));
+    // carol isn't linked and signals aren't sent to all agents by default
+    assert.deepEqual(carolSignals, []);
+  });
+});
+
+test("Subscribe to a node to get its changes as remote signals", async () => {
+  await runScenario(async (scenario) => {
+    const testAppPath = process.cwd() + "/../workdir/generic-dna.happ";
+    const appSource: AppWithOptions = {
+      appBundleSource: { type: "path", value: testAppPath },
+    };
+    const [alice, bob] = await scenario.addPlayersWithApps([
+      appSource,
+      appSource,
+    ]);
+    await scenario.shareAllAgents();
+    const aliceCell = getCellByRoleName(alice, "generic_dna");
+    const bobCell = getCellByRoleName(bob, "generic_dna");
+
+    const bobSignals: SignalKind[] = [];
+    bob.appWs.on("signal", (signal: Signal) => {
+      if (signal.type !== SignalType.App) return;
+      const payload = signal.value.payload as GenericZomeSignal;
+      if (payload.type === "Remote") bobSignals.push(payload.content);
+    });
+
+    const anchor: NodeId = { type: "Anchor", id: "SUBSCRIBED" };
+    await bobCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "subscribe",
+      payload: anchor,
+    });
+    // Subscribing twice keeps a single subscription
+    await bobCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "subscribe",
+      payload: anchor,
+    });
+    await dhtSync([alice, bob], aliceCell.cell_id[0]);
+
+    const subscribers: AgentPubKey[] = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_subscribers",
+      payload: anchor,
+    });
+    assert.deepEqual(
+      subscribers.map((a) => encodeHashToBase64(a)),
+      [encodeHashToBase64(bob.agentPubKey)]
+    );
+
+    // Subscriptions don't show up as regular links of the node
+    await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "create_links_from_node",
+      payload: {
+        src: anchor,
+        links: [
+          linkInputToRustFormat({
+            direction: LinkDirection.To,
+            node_id: { type: "Agent", id: alice.agentPubKey },
+          }),
+        ],
+      },
+    });
+    await dhtSync([alice, bob], aliceCell.cell_id[0]);
+    const linkedAgents: [AgentPubKey, LinkTagContent][] =
+      await aliceCell.callZome({
+        zome_name: "generic_zome",
+        fn_name: "get_linked_agents",
+        payload: { node_id: anchor },
+      });
+    assert.deepEqual(
+      linkedAgents.map(([a]) => encodeHashToBase64(a)),
+      [encodeHashToBase64(alice.agentPubKey)]
+    );
+    const linkedNodeIds: NodeIdAndMetaTag[] = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_all_linked_node_ids",
+      payload: { node_id: anchor },
+    });
+    assert.equal(linkedNodeIds.length, 1);
+    const counts: LinkCounts = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "count_links",
+      payload: { node_id: anchor },
+    });
+    assert.equal(counts.to_agent, 1);
+
+    await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "create_thing",
+      payload: {
+        content: "news",
+        links: [
+          linkInputToRustFormat({
+            direction: LinkDirection.From,
+            node_id: anchor,
+          }),
+        ],
+      },
+    });
+    await pause(2000);
+    assert.isTrue(bobSignals.some((s) => s.type === "LinksCreated"));
+
+    await bobCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "unsubscribe",
+      payload: anchor,
+    });
+    await dhtSync([alice, bob], aliceCell.cell_id[0]);
+    const subscribersAfter: AgentPubKey[] = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_subscribers",
+      payload: anchor,
+    });
+    assert.equal(subscribersAfter.length, 0);
+  });
+});
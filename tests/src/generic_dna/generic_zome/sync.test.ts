@@ -0,0 +1,120 @@
+import { assert, test } from "vitest";
+
+import { encodeHashToBase64 } from "@holochain/client";
+import { AppWithOptions, runScenario } from "@holochain/tryorama";
+
+import { getCellByRoleName } from "./common.js";
+import {
+  LinkDirection,
+  linkInputToRustFormat,
+  NodeChangesSince,
+  NodeId,
+  Thing,
+} from "@holochain/simple-holochain";
+
+test("Get only the changes of nodes since a given timestamp", async () => {
+  await runScenario(async (scenario) => {
+    const testAppPath = process.cwd() + "/../workdir/generic-dna.happ";
+    const appSource: AppWithOptions = {
+      appBundleSource: { type: "path", value: testAppPath },
+    };
+    const [alice] = await scenario.addPlayersWithApps([appSource]);
+    const aliceCell = getCellByRoleName(alice, "generic_dna");
+
+    const textEncoder = new TextEncoder();
+    const anchor: NodeId = { type: "Anchor", id: "SYNC" };
+    const things: Thing[] = [];
+    for (const content of ["first", "second"]) {
+      things.push(
+        await aliceCell.callZome({
+          zome_name: "generic_zome",
+          fn_name: "create_thing",
+          payload: { content },
+        })
+      );
+    }
+    const [first, second] = things.map(
+      (thing): NodeId => ({ type: "Thing", id: thing.id })
+    );
+    await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "create_links_from_node",
+      payload: {
+        src: anchor,
+        links: [first, second].map((node_id) =>
+          linkInputToRustFormat({
+            direction: LinkDirection.To,
+            node_id,
+            tag: textEncoder.encode("item"),
+          })
+        ),
+      },
+    });
+
+    const initial: NodeChangesSince = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_node_changes_since",
+      payload: { node_ids: [anchor, first], since: 0 },
+    });
+    const anchorChanges = initial.changes.find(
+      (c) => c.node_id.type === "Anchor"
+    );
+    assert.equal(anchorChanges!.links_created.length, 2);
+    assert.isAbove(initial.high_water_mark, 0);
+
+    await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "delete_links_from_node",
+      payload: {
+        src: anchor,
+        links: [
+          linkInputToRustFormat({
+            direction: LinkDirection.To,
+            node_id: first,
+            tag: textEncoder.encode("item"),
+          }),
+        ],
+      },
+    });
+    await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "update_thing",
+      payload: { thing_id: things[0].id, updated_content: "first, updated" },
+    });
+
+    const delta: NodeChangesSince = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_node_changes_since",
+      payload: { node_ids: [anchor, first], since: initial.high_water_mark },
+    });
+    const anchorDelta = delta.changes.find((c) => c.node_id.type === "Anchor");
+    assert.equal(anchorDelta!.links_created.length, 0);
+    assert.equal(anchorDelta!.links_deleted.length, 1);
+    assert.equal(
+      encodeHashToBase64(anchorDelta!.links_deleted[0].dst.id as Uint8Array),
+      encodeHashToBase64(things[0].id)
+    );
+    const thingDelta = delta.changes.find((c) => c.node_id.type === "Thing");
+    assert.equal(thingDelta!.thing_updated!.content, "first, updated");
+    assert.isFalse(thingDelta!.thing_deleted);
+
+    // Subscriptions and trash links aren't changes of the nodes
+    await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "subscribe",
+      payload: anchor,
+    });
+    await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "trash_thing",
+      payload: { thing_id: things[0].id, global: false },
+    });
+    const unchanged: NodeChangesSince = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_node_changes_since",
+      payload: { node_ids: [anchor, first], since: delta.high_water_mark },
+    });
+    assert.deepEqual(unchanged.changes, []);
+    assert.equal(unchanged.high_water_mark, delta.high_water_mark);
+  });
+});
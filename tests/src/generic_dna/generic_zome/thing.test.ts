@@ -7,14 +7,18 @@ import { decode, encode } from "@msgpack/msgpack";
 import { getCellByRoleName } from "./common.js";
 import {
   CreateThingInput,
+  DeletedExpired,
   DeleteThingInput,
   LinkDirection,
   LinkInput,
   linkInputToRustFormat,
   LinkTagContent,
+  NodeAndLinkedIds,
   NodeContent,
   NodeId,
+  OrphanAction,
   Thing,
+  ThingDetails,
   UpdateThingInput,
 } from "@holochain/simple-holochain";
 
@@ -59,12 +63,46 @@ test("Create Thing and update it twice", async () => {
     );
     assert.equal(thing.content, thingInput.content);
 
+    // Alice gets the thing from her local databases only
+    const localThing: Thing | undefined = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_latest_thing",
+      payload: { thing_id: thing.id, get_strategy: "Local" },
+    });
+    assert.equal(localThing.content, thingInput.content);
+
+    // The plain thing id of the old signature is still accepted
+    const plainThing: Thing | undefined = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_latest_thing",
+      payload: thing.id,
+    });
+    assert.equal(plainThing.content, thingInput.content);
+
+    // Options that an extern doesn't use are rejected
+    try {
+      await aliceCell.callZome({
+        zome_name: "generic_zome",
+        fn_name: "get_linked_agents",
+        payload: {
+          node_id: { type: "Thing", id: thing.id },
+          include_trashed: true,
+        },
+      });
+      assert.fail("Passing an unsupported option should fail");
+    } catch (e) {
+      assert.include(
+        (e as Error).toString(),
+        "get_linked_agents doesn't support the include_trashed option."
+      );
+    }
+
     // Bob gets the thing
     await dhtSync([alice, bob], aliceCell.cell_id[0]);
     const maybeThing: Thing | undefined = await bobCell.callZome({
       zome_name: "generic_zome",
       fn_name: "get_latest_thing",
-      payload: thing.id,
+      payload: { thing_id: thing.id },
     });
 
     assert.equal(
@@ -89,7 +127,7 @@ test("Create Thing and update it twice", async () => {
     const maybeUpdatedThing: Thing | undefined = await aliceCell.callZome({
       zome_name: "generic_zome",
       fn_name: "get_latest_thing",
-      payload: thing.id,
+      payload: { thing_id: thing.id },
     });
 
     assert.equal(
@@ -117,7 +155,7 @@ test("Create Thing and update it twice", async () => {
     const maybeUpdatedThing2: Thing | undefined = await aliceCell.callZome({
       zome_name: "generic_zome",
       fn_name: "get_latest_thing",
-      payload: thing.id,
+      payload: { thing_id: thing.id },
     });
 
     assert.equal(
@@ -185,7 +223,7 @@ test("Create Thing and a bidirectional link from the creator, then delete it and
       await bobCell.callZome({
         zome_name: "generic_zome",
         fn_name: "get_linked_agents",
-        payload: thingNode,
+        payload: { node_id: thingNode },
       });
     assert(linkedAgents.length === 1);
     assert.equal(
@@ -201,7 +239,7 @@ test("Create Thing and a bidirectional link from the creator, then delete it and
     const linkedNodesFromThing: NodeContent[] = await bobCell.callZome({
       zome_name: "generic_zome",
       fn_name: "get_all_linked_nodes",
-      payload: thingNode,
+      payload: { node_id: thingNode },
     });
     assert(linkedNodesFromThing.length === 1);
     assert.deepEqual(linkedNodesFromThing[0], {
@@ -213,7 +251,7 @@ test("Create Thing and a bidirectional link from the creator, then delete it and
     const linkedNodes: NodeContent[] = await bobCell.callZome({
       zome_name: "generic_zome",
       fn_name: "get_all_linked_nodes",
-      payload: aliceAgentAnchor,
+      payload: { node_id: aliceAgentAnchor },
     });
 
     assert(linkedNodes.length === 1);
@@ -226,7 +264,7 @@ test("Create Thing and a bidirectional link from the creator, then delete it and
     const linkedThings: Thing[] = await bobCell.callZome({
       zome_name: "generic_zome",
       fn_name: "get_linked_things",
-      payload: aliceAgentAnchor,
+      payload: { node_id: aliceAgentAnchor },
     });
     assert(linkedThings.length === 1);
     assert.deepEqual(linkedThings[0], thing);
@@ -235,7 +273,7 @@ test("Create Thing and a bidirectional link from the creator, then delete it and
       await bobCell.callZome({
         zome_name: "generic_zome",
         fn_name: "get_linked_thing_ids",
-        payload: aliceAgentAnchor,
+        payload: { node_id: aliceAgentAnchor },
       });
     assert(linkedThingIds.length === 1);
     assert.equal(
@@ -269,14 +307,14 @@ test("Create Thing and a bidirectional link from the creator, then delete it and
       await bobCell.callZome({
         zome_name: "generic_zome",
         fn_name: "get_linked_agents",
-        payload: thingNode,
+        payload: { node_id: thingNode },
       });
     assert(linkedAgents2.length === 1);
 
     const linkedNodesFromThing2: NodeContent[] = await bobCell.callZome({
       zome_name: "generic_zome",
       fn_name: "get_all_linked_nodes",
-      payload: thingNode,
+      payload: { node_id: thingNode },
     });
     assert(linkedNodesFromThing2.length === 1);
 
@@ -285,14 +323,14 @@ test("Create Thing and a bidirectional link from the creator, then delete it and
     const linkedNodes2: NodeContent[] = await bobCell.callZome({
       zome_name: "generic_zome",
       fn_name: "get_all_linked_nodes",
-      payload: aliceAgentAnchor,
+      payload: { node_id: aliceAgentAnchor },
     });
     assert(linkedNodes2.length === 0);
 
     const linkedThings2: Thing[] = await bobCell.callZome({
       zome_name: "generic_zome",
       fn_name: "get_linked_things",
-      payload: aliceAgentAnchor,
+      payload: { node_id: aliceAgentAnchor },
     });
     assert(linkedThings2.length === 0);
 
@@ -300,9 +338,24 @@ test("Create Thing and a bidirectional link from the creator, then delete it and
       await bobCell.callZome({
         zome_name: "generic_zome",
         fn_name: "get_linked_thing_ids",
-        payload: aliceAgentAnchor,
+        payload: { node_id: aliceAgentAnchor },
       });
     assert(linkedThingIds2.length === 0);
+
+    // Bob can tell that the thing was deleted and by whom
+    const thingDetails: ThingDetails = await bobCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_thing_details",
+      payload: { thing_id: thing.id },
+    });
+    assert.equal(thingDetails.status.type, "Deleted");
+    assert.equal(
+      thingDetails.status.type === "Deleted" &&
+        encodeHashToBase64(thingDetails.status.by),
+      encodeHashToBase64(alice.agentPubKey)
+    );
+    assert.equal(thingDetails.revisions.length, 1);
+    assert.ok(thingDetails.revisions[0].deleted);
   });
 });
 
@@ -359,7 +412,7 @@ test("Create Thing and an anchor, then delete the thing and the anchor link", as
     const linkedNodes: NodeContent[] = await bobCell.callZome({
       zome_name: "generic_zome",
       fn_name: "get_all_linked_nodes",
-      payload: allThingsAnchor,
+      payload: { node_id: allThingsAnchor },
     });
 
     assert(linkedNodes.length === 1);
@@ -372,7 +425,7 @@ test("Create Thing and an anchor, then delete the thing and the anchor link", as
     const linkedThings: Thing[] = await bobCell.callZome({
       zome_name: "generic_zome",
       fn_name: "get_linked_things",
-      payload: allThingsAnchor,
+      payload: { node_id: allThingsAnchor },
     });
     assert(linkedThings.length === 1);
     assert.deepEqual(linkedThings[0], thing);
@@ -381,7 +434,7 @@ test("Create Thing and an anchor, then delete the thing and the anchor link", as
       await bobCell.callZome({
         zome_name: "generic_zome",
         fn_name: "get_linked_thing_ids",
-        payload: allThingsAnchor,
+        payload: { node_id: allThingsAnchor },
       });
     assert(linkedThingIds.length === 1);
     assert.equal(
@@ -414,14 +467,14 @@ test("Create Thing and an anchor, then delete the thing and the anchor link", as
     const linkedNodes2: NodeContent[] = await bobCell.callZome({
       zome_name: "generic_zome",
       fn_name: "get_all_linked_nodes",
-      payload: allThingsAnchor,
+      payload: { node_id: allThingsAnchor },
     });
     assert(linkedNodes2.length === 0);
 
     const linkedThings2: Thing[] = await bobCell.callZome({
       zome_name: "generic_zome",
       fn_name: "get_linked_things",
-      payload: allThingsAnchor,
+      payload: { node_id: allThingsAnchor },
     });
     assert(linkedThings2.length === 0);
 
@@ -429,12 +482,92 @@ test("Create Thing and an anchor, then delete the thing and the anchor link", as
       await bobCell.callZome({
         zome_name: "generic_zome",
         fn_name: "get_linked_thing_ids",
-        payload: allThingsAnchor,
+        payload: { node_id: allThingsAnchor },
       });
     assert(linkedThingIds2.length === 0);
   });
 });
 
+test("Delete the links pointing to a Thing together with it", async () => {
+  await runScenario(async (scenario) => {
+    const testAppPath = process.cwd() + "/../workdir/generic-dna.happ";
+    const appSource: AppWithOptions = {
+      appBundleSource: { type: "path", value: testAppPath },
+    };
+    const [alice, bob] = await scenario.addPlayersWithApps([
+      appSource,
+      appSource,
+    ]);
+    await scenario.shareAllAgents();
+    const aliceCell = getCellByRoleName(alice, "generic_dna");
+    const bobCell = getCellByRoleName(bob, "generic_dna");
+
+    const project: Thing = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "create_thing",
+      payload: { content: "project" },
+    });
+    const projectNode: NodeId = { type: "Thing", id: project.id };
+    // Bob links his note to the project in both directions
+    const note: Thing = await bobCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "create_thing",
+      payload: {
+        content: "note",
+        links: [
+          linkInputToRustFormat({
+            direction: LinkDirection.Bidirectional,
+            node_id: projectNode,
+          }),
+        ],
+      },
+    });
+    const noteNode: NodeId = { type: "Thing", id: note.id };
+    // Alice links an anchor to the project without a backlink
+    const anchor: NodeId = { type: "Anchor", id: "PROJECTS" };
+    await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "create_links_from_node",
+      payload: {
+        src: anchor,
+        links: [
+          linkInputToRustFormat({
+            direction: LinkDirection.To,
+            node_id: projectNode,
+          }),
+        ],
+      },
+    });
+    await dhtSync([alice, bob], aliceCell.cell_id[0]);
+
+    const getLinkedThings = (nodeId: NodeId): Promise<Thing[]> =>
+      aliceCell.callZome({
+        zome_name: "generic_zome",
+        fn_name: "get_linked_things",
+        payload: { node_id: nodeId },
+      });
+    assert.equal((await getLinkedThings(noteNode)).length, 1);
+    assert.equal((await getLinkedThings(anchor)).length, 1);
+
+    const deleteThingInput: DeleteThingInput = {
+      thing_id: project.id,
+      delete_backlinks: true,
+      delete_links_from_creator: false,
+    };
+    await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "delete_thing",
+      payload: deleteThingInput,
+    });
+    await dhtSync([alice, bob], aliceCell.cell_id[0]);
+
+    // Bob's link is found through its backlink stored at the project and
+    // Alice's own link through her source chain
+    assert.equal((await getLinkedThings(noteNode)).length, 0);
+    assert.equal((await getLinkedThings(anchor)).length, 0);
+  });
+});
+
 test("Alice Creates Thing and an anchor link, Bob deletes the thing and the anchor link", async () => {
   await runScenario(async (scenario) => {
     // Construct proper paths for your app.
@@ -503,14 +636,14 @@ test("Alice Creates Thing and an anchor link, Bob deletes the thing and the anch
     const linkedNodes2: NodeContent[] = await aliceCell.callZome({
       zome_name: "generic_zome",
       fn_name: "get_all_linked_nodes",
-      payload: allThingsAnchor,
+      payload: { node_id: allThingsAnchor },
     });
     assert(linkedNodes2.length === 0);
 
     const linkedThings2: Thing[] = await aliceCell.callZome({
       zome_name: "generic_zome",
       fn_name: "get_linked_things",
-      payload: allThingsAnchor,
+      payload: { node_id: allThingsAnchor },
     });
     assert(linkedThings2.length === 0);
 
@@ -518,12 +651,345 @@ test("Alice Creates Thing and an anchor link, Bob deletes the thing and the anch
       await bobCell.callZome({
         zome_name: "generic_zome",
         fn_name: "get_linked_thing_ids",
-        payload: allThingsAnchor,
+        payload: { node_id: allThingsAnchor },
       });
     assert(linkedThingIds2.length === 0);
   });
 });
 
+test("Move a Thing to the trash, restore it and empty the trash", async () => {
+  await runScenario(async (scenario) => {
+    const testAppPath = process.cwd() + "/../workdir/generic-dna.happ";
+    const appSource: AppWithOptions = {
+      appBundleSource: { type: "path", value: testAppPath },
+    };
+    const [alice, bob] = await scenario.addPlayersWithApps([
+      appSource,
+      appSource,
+    ]);
+    await scenario.shareAllAgents();
+    const aliceCell = getCellByRoleName(alice, "generic_dna");
+    const bobCell = getCellByRoleName(bob, "generic_dna");
+
+    const anchor: NodeId = { type: "Anchor", id: "NOTES" };
+    const thing: Thing = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "create_thing",
+      payload: {
+        content: "note",
+        links: [
+          linkInputToRustFormat({
+            direction: LinkDirection.From,
+            node_id: anchor,
+          }),
+        ],
+      },
+    });
+    const getThing = (include_trashed: boolean): Promise<Thing | undefined> =>
+      aliceCell.callZome({
+        zome_name: "generic_zome",
+        fn_name: "get_latest_thing",
+        payload: { thing_id: thing.id, include_trashed },
+      });
+    const getLinkedThings = (include_trashed: boolean): Promise<Thing[]> =>
+      aliceCell.callZome({
+        zome_name: "generic_zome",
+        fn_name: "get_linked_things",
+        payload: { node_id: anchor, include_trashed },
+      });
+
+    await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "trash_thing",
+      payload: { thing_id: thing.id, global: false },
+    });
+    assert.notExists(await getThing(false));
+    assert.equal((await getThing(true))!.content, "note");
+    assert.equal((await getLinkedThings(false)).length, 0);
+    assert.equal((await getLinkedThings(true)).length, 1);
+    const getNodeAndLinkedNodeIds = (
+      nodeId: NodeId,
+      include_trashed: boolean
+    ): Promise<NodeAndLinkedIds | undefined> =>
+      aliceCell.callZome({
+        zome_name: "generic_zome",
+        fn_name: "get_node_and_linked_node_ids",
+        payload: { node_id: nodeId, expand: { depth: 1 }, include_trashed },
+      });
+    const anchorAndLinked = await getNodeAndLinkedNodeIds(anchor, false);
+    assert.equal(anchorAndLinked!.linked_node_ids.length, 0);
+    assert.equal(anchorAndLinked!.expanded!.length, 0);
+    assert.notExists(
+      await getNodeAndLinkedNodeIds({ type: "Thing", id: thing.id }, false)
+    );
+    const anchorAndTrashed = await getNodeAndLinkedNodeIds(anchor, true);
+    assert.equal(anchorAndTrashed!.linked_node_ids.length, 1);
+    assert.equal(anchorAndTrashed!.expanded!.length, 1);
+    // The link to the trash anchor isn't among the links of the Thing
+    const thingAndTrashed = await getNodeAndLinkedNodeIds(
+      { type: "Thing", id: thing.id },
+      true
+    );
+    assert.equal(thingAndTrashed!.linked_node_ids.length, 1);
+    assert.deepEqual(thingAndTrashed!.linked_node_ids[0].node_id, anchor);
+    const originalThing: Thing | undefined = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_original_thing",
+      payload: { thing_id: thing.id },
+    });
+    assert.notExists(originalThing);
+
+    // The trash of alice doesn't hide the Thing from bob
+    await dhtSync([alice, bob], aliceCell.cell_id[0]);
+    const thingForBob: Thing | undefined = await bobCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_latest_thing",
+      payload: { thing_id: thing.id },
+    });
+    assert.equal(thingForBob!.content, "note");
+    const linkedThingsForBob: Thing[] = await bobCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_linked_things",
+      payload: { node_id: anchor },
+    });
+    assert.equal(linkedThingsForBob.length, 1);
+
+    const trash: Thing[] = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_trash",
+      payload: { global: false },
+    });
+    assert.equal(trash.length, 1);
+
+    // Restoring the Thing leaves it in the trash of bob
+    await bobCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "trash_thing",
+      payload: { thing_id: thing.id, global: false },
+    });
+    await dhtSync([alice, bob], aliceCell.cell_id[0]);
+    await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "restore_thing",
+      payload: thing.id,
+    });
+    assert.equal((await getThing(false))!.content, "note");
+    assert.equal((await getLinkedThings(false)).length, 1);
+    await dhtSync([alice, bob], aliceCell.cell_id[0]);
+    const bobTrash: Thing[] = await bobCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_trash",
+      payload: { global: false },
+    });
+    assert.equal(bobTrash.length, 1);
+
+    await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "trash_thing",
+      payload: { thing_id: thing.id, global: false },
+    });
+    const deletedThingIds: ActionHash[] = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "empty_trash",
+      payload: { global: false },
+    });
+    assert.deepEqual(
+      deletedThingIds.map(encodeHashToBase64),
+      [encodeHashToBase64(thing.id)]
+    );
+    assert.notExists(await getThing(true));
+  });
+});
+
+test("Expired Things and links are treated as absent and deleted on cleanup", async () => {
+  await runScenario(async (scenario) => {
+    const testAppPath = process.cwd() + "/../workdir/generic-dna.happ";
+    const appSource: AppWithOptions = {
+      appBundleSource: { type: "path", value: testAppPath },
+    };
+    const [alice] = await scenario.addPlayersWithApps([appSource]);
+    const aliceCell = getCellByRoleName(alice, "generic_dna");
+
+    const anchor: NodeId = { type: "Anchor", id: "EPHEMERAL" };
+    // Timestamps are in microseconds
+    const expiredAt = (Date.now() - 1000) * 1000;
+    const liveThing: Thing = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "create_thing",
+      payload: {
+        content: "live",
+        links: [
+          linkInputToRustFormat({
+            direction: LinkDirection.From,
+            node_id: anchor,
+          }),
+        ],
+      },
+    });
+    const expiredThing: Thing = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "create_thing",
+      payload: {
+        content: "expired",
+        expires_at: expiredAt,
+        links: [
+          linkInputToRustFormat({
+            direction: LinkDirection.From,
+            node_id: anchor,
+            expires_at: expiredAt,
+          }),
+        ],
+      },
+    });
+    assert.equal(expiredThing.expires_at, expiredAt);
+
+    const linkedThings: Thing[] = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_linked_things",
+      payload: { node_id: anchor },
+    });
+    assert.deepEqual(
+      linkedThings.map((thing) => thing.content),
+      ["live"]
+    );
+    const maybeExpiredThing: Thing | undefined = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_latest_thing",
+      payload: { thing_id: expiredThing.id },
+    });
+    assert.notExists(maybeExpiredThing);
+    const expiredDetails: ThingDetails = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_thing_details",
+      payload: { thing_id: expiredThing.id },
+    });
+    assert.deepEqual(expiredDetails.status, { type: "Expired", at: expiredAt });
+
+    const deleted: DeletedExpired = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "delete_expired",
+      payload: null,
+    });
+    assert.deepEqual(
+      deleted.thing_ids.map(encodeHashToBase64),
+      [encodeHashToBase64(expiredThing.id)]
+    );
+    assert.equal(deleted.link_action_hashes.length, 1);
+    const liveThingAfterCleanup: Thing | undefined = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_latest_thing",
+      payload: { thing_id: liveThing.id },
+    });
+    assert.equal(liveThingAfterCleanup!.content, "live");
+
+    const deletedAgain: DeletedExpired = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "delete_expired",
+      payload: null,
+    });
+    assert.deepEqual(deletedAgain, { thing_ids: [], link_action_hashes: [] });
+  });
+});
+
+test("Find the Things without inbound links, then re-link and delete them", async () => {
+  await runScenario(async (scenario) => {
+    const testAppPath = process.cwd() + "/../workdir/generic-dna.happ";
+    const appSource: AppWithOptions = {
+      appBundleSource: { type: "path", value: testAppPath },
+    };
+    const [alice, bob] = await scenario.addPlayersWithApps([
+      appSource,
+      appSource,
+    ]);
+    await scenario.shareAllAgents();
+    const aliceCell = getCellByRoleName(alice, "generic_dna");
+    const bobCell = getCellByRoleName(bob, "generic_dna");
+
+    const createThing = (content: string, links?: LinkInput[]): Promise<Thing> =>
+      aliceCell.callZome({
+        zome_name: "generic_zome",
+        fn_name: "create_thing",
+        payload: { content, links: links?.map(linkInputToRustFormat) },
+      });
+    const findMyOrphans = (action?: OrphanAction): Promise<Thing[]> =>
+      aliceCell.callZome({
+        zome_name: "generic_zome",
+        fn_name: "find_my_orphans",
+        payload: { action },
+      });
+
+    await createThing("linked", [
+      {
+        direction: LinkDirection.From,
+        node_id: { type: "Anchor", id: "LINKED" },
+      },
+    ]);
+    const orphan = await createThing("orphan");
+    assert.deepEqual(
+      (await findMyOrphans()).map((thing) => thing.content),
+      ["orphan"]
+    );
+
+    const adopted = await findMyOrphans({ type: "Relink", anchor: "ADOPTED" });
+    assert.equal(adopted.length, 1);
+    const adoptedThings: Thing[] = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_linked_things",
+      payload: { node_id: { type: "Anchor", id: "ADOPTED" } },
+    });
+    assert.equal(
+      encodeHashToBase64(adoptedThings[0].id),
+      encodeHashToBase64(orphan.id)
+    );
+    assert.deepEqual(await findMyOrphans(), []);
+
+    const forgotten = await createThing("forgotten");
+    const linkedByBob = await createThing("linked by bob");
+    await dhtSync([alice, bob], aliceCell.cell_id[0]);
+    await bobCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "create_links_from_node",
+      payload: {
+        src: { type: "Anchor", id: "BOBS_THINGS" },
+        links: [
+          linkInputToRustFormat({
+            direction: LinkDirection.To,
+            node_id: { type: "Thing", id: linkedByBob.id },
+          }),
+        ],
+      },
+    });
+    await dhtSync([alice, bob], aliceCell.cell_id[0]);
+
+    // Bob's link is not stored at the Thing, so it is reported as an orphan
+    // as well and only the confirmed orphan gets deleted
+    const reported = await findMyOrphans();
+    assert.sameMembers(
+      reported.map((thing) => encodeHashToBase64(thing.id)),
+      [encodeHashToBase64(forgotten.id), encodeHashToBase64(linkedByBob.id)]
+    );
+    await findMyOrphans({ type: "Delete", thing_ids: [forgotten.id] });
+    const maybeForgotten: Thing | undefined = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_latest_thing",
+      payload: { thing_id: forgotten.id },
+    });
+    assert.notExists(maybeForgotten);
+    const maybeLinkedByBob: Thing | undefined = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_latest_thing",
+      payload: { thing_id: linkedByBob.id },
+    });
+    assert.equal(maybeLinkedByBob!.content, "linked by bob");
+    const bobsThings: Thing[] = await bobCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_linked_things",
+      payload: { node_id: { type: "Anchor", id: "BOBS_THINGS" } },
+    });
+    assert.equal(bobsThings.length, 1);
+  });
+});
+
 // This test doesn't really make sense
 // test("Create Thing and an anchor, then IMMEDIATELY delete the thing and the anchor link", async () => {
 //   await runScenario(async (scenario) => {
@@ -592,7 +1058,7 @@ test("Alice Creates Thing and an anchor link, Bob deletes the thing and the anch
 //     const linkedNodes: NodeContent[] = await aliceCell.callZome({
 //       zome_name: "generic_zome",
 //       fn_name: "get_all_linked_nodes",
-//       payload: allThingsAnchor,
+//       payload: { node_id: allThingsAnchor },
 //     });
 
 //     assert(linkedNodes.length === 0);
@@ -1,16 +1,25 @@
 import { assert, test } from "vitest";
 
 import { ActionHash, encodeHashToBase64 } from "@holochain/client";
-import { AppWithOptions, runScenario } from "@holochain/tryorama";
+import { AppWithOptions, dhtSync, runScenario } from "@holochain/tryorama";
 import { decode, encode } from "@msgpack/msgpack";
 
 import { getCellByRoleName } from "./common.js";
 import {
   CreateThingInput,
+  GraphIssue,
   LinkDirection,
+  LinkCounts,
+  LinkCursor,
+  LinkHistoryEntry,
   LinkInput,
   linkInputToRustFormat,
   LinkTagContent,
+  NodeContent,
+  NodeId,
+  NodeIdAndMetaTag,
+  NodeLinkMeta,
+  Page,
   Thing,
 } from "@holochain/simple-holochain";
 
@@ -70,7 +79,7 @@ test("Create a bidirectional link between to Things and verify correctness of th
       await aliceCell.callZome({
         zome_name: "generic_zome",
         fn_name: "get_linked_thing_ids",
-        payload: { type: "Thing", id: thing2.id },
+        payload: { node_id: { type: "Thing", id: thing2.id } },
       });
 
     assert(linkedThingIds.length === 1);
@@ -94,7 +103,7 @@ test("Create a bidirectional link between to Things and verify correctness of th
       await aliceCell.callZome({
         zome_name: "generic_zome",
         fn_name: "get_linked_thing_ids",
-        payload: { type: "Thing", id: thing1.id },
+        payload: { node_id: { type: "Thing", id: thing1.id } },
       });
 
     assert(linkedThingIds2.length === 1);
@@ -116,4 +125,757 @@ test("Create a bidirectional link between to Things and verify correctness of th
   });
 });
 
+test("Creating the same link twice with dedupe only creates it once and has_link finds it", async () => {
+  await runScenario(async (scenario) => {
+    const testAppPath = process.cwd() + "/../workdir/generic-dna.happ";
+    const appSource: AppWithOptions = {
+      appBundleSource: { type: "path", value: testAppPath },
+    };
+    const [alice] = await scenario.addPlayersWithApps([appSource]);
+    const aliceCell = getCellByRoleName(alice, "generic_dna");
+
+    const thing: Thing = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "create_thing",
+      payload: { content: "thing 1" },
+    });
+
+    const linkInput: LinkInput = {
+      direction: LinkDirection.To,
+      node_id: { type: "Thing", id: thing.id },
+      tag: encode("testtag"),
+      dedupe: true,
+    };
+    const createLinksInput = {
+      src: { type: "Anchor", id: "ALL_THINGS" },
+      links: [linkInputToRustFormat(linkInput)],
+    };
+    await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "create_links_from_node",
+      payload: createLinksInput,
+    });
+    await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "create_links_from_node",
+      payload: createLinksInput,
+    });
+
+    const linkedThingIds: [ActionHash, LinkTagContent][] =
+      await aliceCell.callZome({
+        zome_name: "generic_zome",
+        fn_name: "get_linked_thing_ids",
+        payload: { node_id: { type: "Anchor", id: "ALL_THINGS" } },
+      });
+    assert(linkedThingIds.length === 1);
+
+    const hasLink: boolean = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "has_link",
+      payload: {
+        src: { type: "Anchor", id: "ALL_THINGS" },
+        dst: { type: "Thing", id: thing.id },
+        tag: encode("testtag"),
+      },
+    });
+    assert(hasLink);
+
+    const hasLinkOtherTag: boolean = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "has_link",
+      payload: {
+        src: { type: "Anchor", id: "ALL_THINGS" },
+        dst: { type: "Thing", id: thing.id },
+        tag: encode("othertag"),
+      },
+    });
+    assert(!hasLinkOtherTag);
+  });
+});
+
+test("Only links whose tag starts with the tag_prefix are returned", async () => {
+  await runScenario(async (scenario) => {
+    const testAppPath = process.cwd() + "/../workdir/generic-dna.happ";
+    const appSource: AppWithOptions = {
+      appBundleSource: { type: "path", value: testAppPath },
+    };
+    const [alice] = await scenario.addPlayersWithApps([appSource]);
+    const aliceCell = getCellByRoleName(alice, "generic_dna");
+
+    const textEncoder = new TextEncoder();
+    const anchor = { type: "Anchor", id: "ALL_THINGS" };
+    for (const tag of ["comment:1", "comment:2", "reaction:1"]) {
+      await aliceCell.callZome({
+        zome_name: "generic_zome",
+        fn_name: "create_thing",
+        payload: {
+          content: tag,
+          links: [
+            linkInputToRustFormat({
+              direction: LinkDirection.From,
+              node_id: anchor as NodeId,
+              tag: textEncoder.encode(tag),
+            }),
+          ],
+        },
+      });
+    }
+    // A link without tag must not be matched by any non-empty prefix
+    await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "create_thing",
+      payload: {
+        content: "untagged",
+        links: [
+          linkInputToRustFormat({
+            direction: LinkDirection.From,
+            node_id: anchor as NodeId,
+          }),
+        ],
+      },
+    });
+
+    const comments: Thing[] = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_linked_things",
+      payload: { node_id: anchor, tag_prefix: textEncoder.encode("comment:") },
+    });
+    assert.equal(comments.length, 2);
+    assert.sameMembers(
+      comments.map((t) => t.content),
+      ["comment:1", "comment:2"]
+    );
+
+    const allLinkedNodeIds: NodeIdAndMetaTag[] = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_all_linked_node_ids",
+      payload: { node_id: anchor },
+    });
+    assert.equal(allLinkedNodeIds.length, 4);
+  });
+});
+
+test("Creating a link with a tag that exceeds the size budget fails with TagTooLarge", async () => {
+  await runScenario(async (scenario) => {
+    const testAppPath = process.cwd() + "/../workdir/generic-dna.happ";
+    const appSource: AppWithOptions = {
+      appBundleSource: { type: "path", value: testAppPath },
+    };
+    const [alice] = await scenario.addPlayersWithApps([appSource]);
+    const aliceCell = getCellByRoleName(alice, "generic_dna");
+
+    const thing: Thing = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "create_thing",
+      payload: { content: "thing 1" },
+    });
+    const thingNode = { type: "Thing", id: thing.id };
+
+    const maxUserTagSize: number = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "max_user_tag_size",
+      payload: thingNode,
+    });
+    assert(maxUserTagSize > 0 && maxUserTagSize < 1000);
+
+    // A tag of exactly the maximum size can be used
+    await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "create_links_from_node",
+      payload: {
+        src: { type: "Anchor", id: "ALL_THINGS" },
+        links: [
+          linkInputToRustFormat({
+            direction: LinkDirection.Bidirectional,
+            node_id: thingNode as NodeId,
+            tag: new Uint8Array(maxUserTagSize),
+          }),
+        ],
+      },
+    });
+
+    try {
+      await aliceCell.callZome({
+        zome_name: "generic_zome",
+        fn_name: "create_links_from_node",
+        payload: {
+          src: { type: "Anchor", id: "ALL_THINGS" },
+          links: [
+            linkInputToRustFormat({
+              direction: LinkDirection.Bidirectional,
+              node_id: thingNode as NodeId,
+              tag: new Uint8Array(maxUserTagSize + 1),
+            }),
+          ],
+        },
+      });
+      assert.fail("Creating a link with a too large tag should fail");
+    } catch (e) {
+      assert.include(
+        (e as Error).toString(),
+        `TagTooLarge { max: ${maxUserTagSize}, actual: ${maxUserTagSize + 1} }`
+      );
+    }
+  });
+});
+
+test("Page through the Things linked from an anchor", async () => {
+  await runScenario(async (scenario) => {
+    const testAppPath = process.cwd() + "/../workdir/generic-dna.happ";
+    const appSource: AppWithOptions = {
+      appBundleSource: { type: "path", value: testAppPath },
+    };
+    const [alice] = await scenario.addPlayersWithApps([appSource]);
+    const aliceCell = getCellByRoleName(alice, "generic_dna");
+
+    const anchor = { type: "Anchor", id: "ALL_THINGS" };
+    for (let i = 0; i < 5; i++) {
+      await aliceCell.callZome({
+        zome_name: "generic_zome",
+        fn_name: "create_thing",
+        payload: {
+          content: `thing ${i}`,
+          links: [
+            linkInputToRustFormat({
+              direction: LinkDirection.From,
+              node_id: anchor as NodeId,
+            }),
+          ],
+        },
+      });
+    }
+
+    const contents: string[] = [];
+    let cursor: LinkCursor | undefined = undefined;
+    let pages = 0;
+    do {
+      const page: Page<Thing> = await aliceCell.callZome({
+        zome_name: "generic_zome",
+        fn_name: "get_linked_things_page",
+        payload: {
+          query: { node_id: anchor },
+          limit: 2,
+          cursor,
+          order: { type: "Descending" },
+        },
+      });
+      assert(page.items.length <= 2);
+      contents.push(...page.items.map((t) => t.content));
+      cursor = page.next_cursor;
+      pages += 1;
+    } while (cursor);
+
+    assert.equal(pages, 3);
+    assert.deepEqual(contents, [
+      "thing 4",
+      "thing 3",
+      "thing 2",
+      "thing 1",
+      "thing 0",
+    ]);
+  });
+});
+
+test("Fill pages of linked Things past trashed Things", async () => {
+  await runScenario(async (scenario) => {
+    const testAppPath = process.cwd() + "/../workdir/generic-dna.happ";
+    const appSource: AppWithOptions = {
+      appBundleSource: { type: "path", value: testAppPath },
+    };
+    const [alice] = await scenario.addPlayersWithApps([appSource]);
+    const aliceCell = getCellByRoleName(alice, "generic_dna");
+
+    const anchor = { type: "Anchor", id: "ALL_THINGS" };
+    const things: Thing[] = [];
+    for (let i = 0; i < 5; i++) {
+      things.push(
+        await aliceCell.callZome({
+          zome_name: "generic_zome",
+          fn_name: "create_thing",
+          payload: {
+            content: `thing ${i}`,
+            links: [
+              linkInputToRustFormat({
+                direction: LinkDirection.From,
+                node_id: anchor as NodeId,
+              }),
+            ],
+          },
+        })
+      );
+    }
+    await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "trash_thing",
+      payload: { thing_id: things[3].id, global: false },
+    });
+
+    const pages: string[][] = [];
+    let cursor: LinkCursor | undefined = undefined;
+    do {
+      const page: Page<Thing> = await aliceCell.callZome({
+        zome_name: "generic_zome",
+        fn_name: "get_linked_things_page",
+        payload: {
+          query: { node_id: anchor },
+          limit: 2,
+          cursor,
+          order: { type: "Descending" },
+        },
+      });
+      pages.push(page.items.map((t) => t.content));
+      cursor = page.next_cursor;
+    } while (cursor);
+
+    assert.deepEqual(pages, [
+      ["thing 4", "thing 2"],
+      ["thing 1", "thing 0"],
+    ]);
+  });
+});
+
+test("Filter linked Things by author and creation time of the link", async () => {
+  await runScenario(async (scenario) => {
+    const testAppPath = process.cwd() + "/../workdir/generic-dna.happ";
+    const appSource: AppWithOptions = {
+      appBundleSource: { type: "path", value: testAppPath },
+    };
+    const [alice, bob] = await scenario.addPlayersWithApps([
+      appSource,
+      appSource,
+    ]);
+    await scenario.shareAllAgents();
+    const aliceCell = getCellByRoleName(alice, "generic_dna");
+    const bobCell = getCellByRoleName(bob, "generic_dna");
+
+    const anchor = { type: "Anchor", id: "ALL_THINGS" };
+    const createThingUnderAnchor = (cell, content: string): Promise<Thing> =>
+      cell.callZome({
+        zome_name: "generic_zome",
+        fn_name: "create_thing",
+        payload: {
+          content,
+          links: [
+            linkInputToRustFormat({
+              direction: LinkDirection.From,
+              node_id: anchor as NodeId,
+            }),
+          ],
+        },
+      });
+
+    await createThingUnderAnchor(aliceCell, "alice 1");
+    // Timestamps are in microseconds
+    const checkpoint = Date.now() * 1000;
+    await createThingUnderAnchor(bobCell, "bob 1");
+    await dhtSync([alice, bob], aliceCell.cell_id[0]);
+
+    const bobsThings: Thing[] = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_linked_things",
+      payload: { node_id: anchor, author: bob.agentPubKey },
+    });
+    assert.deepEqual(
+      bobsThings.map((t) => t.content),
+      ["bob 1"]
+    );
+
+    const newThings: Thing[] = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_linked_things",
+      payload: { node_id: anchor, after: checkpoint },
+    });
+    assert.deepEqual(
+      newThings.map((t) => t.content),
+      ["bob 1"]
+    );
+
+    const oldThings: Thing[] = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_linked_things",
+      payload: { node_id: anchor, before: checkpoint },
+    });
+    assert.deepEqual(
+      oldThings.map((t) => t.content),
+      ["alice 1"]
+    );
+
+    const allThings: Thing[] = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_linked_things",
+      payload: { node_id: anchor, after: 0 },
+    });
+    assert.equal(allThings.length, 2);
+  });
+});
+
+test("Sort linked Things by a number in the link tag and by creation time", async () => {
+  await runScenario(async (scenario) => {
+    const testAppPath = process.cwd() + "/../workdir/generic-dna.happ";
+    const appSource: AppWithOptions = {
+      appBundleSource: { type: "path", value: testAppPath },
+    };
+    const [alice] = await scenario.addPlayersWithApps([appSource]);
+    const aliceCell = getCellByRoleName(alice, "generic_dna");
+
+    const textEncoder = new TextEncoder();
+    const anchor = { type: "Anchor", id: "ALL_THINGS" };
+    for (const [content, priority] of [
+      ["b", "10"],
+      ["c", "2"],
+      ["a", "33"],
+    ]) {
+      await aliceCell.callZome({
+        zome_name: "generic_zome",
+        fn_name: "create_thing",
+        payload: {
+          content,
+          links: [
+            linkInputToRustFormat({
+              direction: LinkDirection.From,
+              node_id: anchor as NodeId,
+              tag: textEncoder.encode(`priority:${priority}`),
+            }),
+          ],
+        },
+      });
+    }
+
+    const byPriority: Thing[] = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_linked_things",
+      payload: {
+        node_id: anchor,
+        sort: {
+          key: { type: "TagNumber", skip: "priority:".length },
+          order: { type: "Descending" },
+        },
+      },
+    });
+    assert.deepEqual(
+      byPriority.map((t) => t.content),
+      ["a", "b", "c"]
+    );
+
+    const byCreatedAt: NodeContent[] = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_all_linked_nodes",
+      payload: {
+        node_id: anchor,
+        sort: { key: { type: "CreatedAt" }, order: { type: "Ascending" } },
+      },
+    });
+    assert.deepEqual(
+      byCreatedAt.map((n) => n.type === "Thing" && n.content.content),
+      ["b", "c", "a"]
+    );
+  });
+});
+
+test("Count the links from a node per link type", async () => {
+  await runScenario(async (scenario) => {
+    const testAppPath = process.cwd() + "/../workdir/generic-dna.happ";
+    const appSource: AppWithOptions = {
+      appBundleSource: { type: "path", value: testAppPath },
+    };
+    const [alice] = await scenario.addPlayersWithApps([appSource]);
+    const aliceCell = getCellByRoleName(alice, "generic_dna");
+
+    const textEncoder = new TextEncoder();
+    const thing: Thing = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "create_thing",
+      payload: { content: "post" },
+    });
+    const thingNode = { type: "Thing", id: thing.id };
+    for (let i = 0; i < 3; i++) {
+      await aliceCell.callZome({
+        zome_name: "generic_zome",
+        fn_name: "create_thing",
+        payload: {
+          content: `comment ${i}`,
+          links: [
+            linkInputToRustFormat({
+              direction: LinkDirection.From,
+              node_id: thingNode as NodeId,
+              tag: textEncoder.encode("comment"),
+            }),
+          ],
+        },
+      });
+    }
+    await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "create_links_from_node",
+      payload: {
+        src: thingNode,
+        links: [
+          linkInputToRustFormat({
+            direction: LinkDirection.To,
+            node_id: { type: "Agent", id: alice.agentPubKey },
+            tag: textEncoder.encode("member"),
+          }),
+        ],
+      },
+    });
+
+    const counts: LinkCounts = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "count_links",
+      payload: { node_id: thingNode },
+    });
+    assert.equal(counts.to_thing, 3);
+    assert.equal(counts.to_agent, 1);
+    assert.equal(counts.to_anchor, 0);
+
+    const [commentCounts]: LinkCounts[] = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "batch_count_links",
+      payload: [
+        {
+          node_id: thingNode,
+          link_type: "ToThing",
+          tag_prefix: textEncoder.encode("comment"),
+        },
+      ],
+    });
+    assert.equal(commentCounts.to_thing, 3);
+    assert.notExists(commentCounts.to_agent);
+  });
+});
+
+test("Insert and move items of an ordered collection", async () => {
+  await runScenario(async (scenario) => {
+    const testAppPath = process.cwd() + "/../workdir/generic-dna.happ";
+    const appSource: AppWithOptions = {
+      appBundleSource: { type: "path", value: testAppPath },
+    };
+    const [alice] = await scenario.addPlayersWithApps([appSource]);
+    const aliceCell = getCellByRoleName(alice, "generic_dna");
+
+    const tag = new TextEncoder().encode("column");
+    const column = { type: "Anchor", id: "KANBAN_COLUMN" };
+    const cards: Record<string, NodeId> = {};
+    for (const content of ["a", "b", "c"]) {
+      const thing: Thing = await aliceCell.callZome({
+        zome_name: "generic_zome",
+        fn_name: "create_thing",
+        payload: { content },
+      });
+      cards[content] = { type: "Thing", id: thing.id };
+    }
+
+    const getColumn = async () => {
+      const things: Thing[] = await aliceCell.callZome({
+        zome_name: "generic_zome",
+        fn_name: "get_linked_things",
+        payload: { node_id: column },
+      });
+      return things.map((t) => t.content);
+    };
+
+    for (const content of ["a", "c"]) {
+      await aliceCell.callZome({
+        zome_name: "generic_zome",
+        fn_name: "insert_after",
+        payload: {
+          collection: column,
+          item: cards[content],
+          tag,
+          direction: { type: "To" },
+        },
+      });
+    }
+    const inserted: NodeLinkMeta = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "insert_before",
+      payload: {
+        collection: column,
+        item: cards["b"],
+        tag,
+        direction: { type: "Bidirectional" },
+        reference: cards["c"],
+      },
+    });
+    assert.ok(inserted.meta_tag.position);
+    assert.deepEqual(await getColumn(), ["a", "b", "c"]);
+
+    try {
+      await aliceCell.callZome({
+        zome_name: "generic_zome",
+        fn_name: "insert_after",
+        payload: {
+          collection: column,
+          item: cards["a"],
+          tag,
+          direction: { type: "To" },
+          reference: cards["c"],
+        },
+      });
+      assert.fail("Inserting an item twice should fail");
+    } catch (e) {
+      assert.include(String(e), "already part of the collection");
+    }
+    assert.deepEqual(await getColumn(), ["a", "b", "c"]);
+
+    await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "move_item",
+      payload: { collection: column, item: cards["c"], tag },
+    });
+    assert.deepEqual(await getColumn(), ["c", "a", "b"]);
+
+    await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "move_item",
+      payload: { collection: column, item: cards["a"], tag, after: cards["b"] },
+    });
+    assert.deepEqual(await getColumn(), ["c", "b", "a"]);
+  });
+});
+
+test("Get the history of the links from a node including deletions", async () => {
+  await runScenario(async (scenario) => {
+    const testAppPath = process.cwd() + "/../workdir/generic-dna.happ";
+    const appSource: AppWithOptions = {
+      appBundleSource: { type: "path", value: testAppPath },
+    };
+    const [alice] = await scenario.addPlayersWithApps([appSource]);
+    const aliceCell = getCellByRoleName(alice, "generic_dna");
+
+    const textEncoder = new TextEncoder();
+    const anchor: NodeId = { type: "Anchor", id: "HISTORY" };
+    const agent: NodeId = { type: "Agent", id: alice.agentPubKey };
+    const links = ["owner", "member"].map((tag) =>
+      linkInputToRustFormat({
+        direction: LinkDirection.To,
+        node_id: agent,
+        tag: textEncoder.encode(tag),
+      })
+    );
+    await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "create_links_from_node",
+      payload: { src: anchor, links },
+    });
+    await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "delete_links_from_node",
+      payload: { src: anchor, links: [links[0]] },
+    });
+
+    const history: LinkHistoryEntry[] = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_link_history",
+      payload: { node_id: anchor, link_type: "ToAgent" },
+    });
+    assert.equal(history.length, 2);
+    const [owner, member] = ["owner", "member"].map((tag) =>
+      history.find(
+        (entry) =>
+          new TextDecoder().decode(entry.link.meta_tag.tag) === tag
+      )
+    );
+    assert.equal(owner!.deletes.length, 1);
+    assert.equal(
+      encodeHashToBase64(owner!.deletes[0].deleted_by),
+      encodeHashToBase64(alice.agentPubKey)
+    );
+    assert.isAtLeast(owner!.deletes[0].deleted_at, owner!.created_at);
+    assert.deepEqual(member!.deletes, []);
+
+    const liveLinks: NodeIdAndMetaTag[] = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_all_linked_node_ids",
+      payload: { node_id: anchor },
+    });
+    assert.equal(liveLinks.length, 1);
+  });
+});
+
+test("Repair the broken links of a node", async () => {
+  await runScenario(async (scenario) => {
+    const testAppPath = process.cwd() + "/../workdir/generic-dna.happ";
+    const appSource: AppWithOptions = {
+      appBundleSource: { type: "path", value: testAppPath },
+    };
+    const [alice] = await scenario.addPlayersWithApps([appSource]);
+    const aliceCell = getCellByRoleName(alice, "generic_dna");
+
+    const textEncoder = new TextEncoder();
+    const anchor: NodeId = { type: "Anchor", id: "REPAIR" };
+    const other: NodeId = { type: "Anchor", id: "REPAIR_OTHER" };
+    const thing: Thing = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "create_thing",
+      payload: { content: "to be deleted" },
+    });
+    await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "create_links_from_node",
+      payload: {
+        src: anchor,
+        links: [other, { type: "Thing", id: thing.id } as NodeId].map(
+          (node_id) =>
+            linkInputToRustFormat({
+              direction: LinkDirection.Bidirectional,
+              node_id,
+              tag: textEncoder.encode("pair"),
+            })
+        ),
+      },
+    });
+
+    // Delete only the backlink of the first link and the Thing without its
+    // backlinks
+    await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "delete_links_from_node",
+      payload: {
+        src: other,
+        links: [
+          {
+            direction: { type: "To" },
+            node_id: anchor,
+            tag: textEncoder.encode("pair"),
+          },
+        ],
+      },
+    });
+    await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "delete_thing",
+      payload: {
+        thing_id: thing.id,
+        delete_backlinks: false,
+        delete_links_from_creator: false,
+      },
+    });
+
+    const repairGraph = (fix: boolean): Promise<GraphIssue[]> =>
+      aliceCell.callZome({
+        zome_name: "generic_zome",
+        fn_name: "repair_graph",
+        payload: { node_ids: [anchor], fix },
+      });
+    const reported = await repairGraph(false);
+    assert.sameMembers(
+      reported.map((issue) => issue.kind.type),
+      ["MissingBacklink", "LinkToDeletedThing"]
+    );
+    assert.isTrue(reported.every((issue) => !issue.repaired));
+
+    const repaired = await repairGraph(true);
+    assert.equal(repaired.length, 2);
+    assert.isTrue(repaired.every((issue) => issue.repaired));
+    const liveLinks: NodeIdAndMetaTag[] = await aliceCell.callZome({
+      zome_name: "generic_zome",
+      fn_name: "get_all_linked_node_ids",
+      payload: { node_id: anchor },
+    });
+    assert.equal(liveLinks.length, 0);
+    assert.deepEqual(await repairGraph(true), []);
+  });
+});
+
 // TODO test all other combinations of link creation